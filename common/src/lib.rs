@@ -12,6 +12,145 @@ pub mod adv_io {
     }
 }
 
+pub mod lexer {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum TokenKind {
+        /// Letters, digits and `_`, starting with a letter
+        Ident,
+        /// ASCII digits, no sign
+        Int,
+        /// Any other single character except whitespace
+        Punct(char),
+    }
+
+    /// Range of a token in the line
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Token<'a> {
+        pub kind: TokenKind,
+        pub text: &'a str,
+        /// Bytes of the line, to slice it
+        pub span: Span,
+        /// Characters of the line, to point at the token
+        pub chars: Span,
+    }
+
+    impl<'a> Token<'a> {
+        /// 1-based column of the first character
+        pub fn column(&self) -> usize {
+            return self.chars.start + 1;
+        }
+
+        pub fn is_punct(&self, c: char) -> bool {
+            return self.kind == TokenKind::Punct(c);
+        }
+    }
+
+    pub fn lex(line: &str) -> Vec<Token<'_>> {
+        let mut tokens = Vec::new();
+        let mut chars = line.char_indices().enumerate().peekable();
+
+        while let Some((first, (start, c))) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let kind = if c.is_ascii_digit() {
+                TokenKind::Int
+            } else if c.is_alphabetic() {
+                TokenKind::Ident
+            } else {
+                TokenKind::Punct(c)
+            };
+
+            let mut end = start + c.len_utf8();
+            let mut last = first;
+            if kind != TokenKind::Punct(c) {
+                while let Some((n, (i, next))) = chars.peek() {
+                    let more = match kind {
+                        TokenKind::Int => next.is_ascii_digit(),
+                        _ => next.is_alphanumeric() || *next == '_',
+                    };
+                    if !more {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    last = *n;
+                    chars.next();
+                }
+            }
+
+            tokens.push(Token {
+                kind,
+                text: &line[start..end],
+                span: Span { start, end },
+                chars: Span {
+                    start: first,
+                    end: last + 1,
+                },
+            });
+        }
+
+        return tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests_lexer {
+    use super::lexer::{lex, Span, TokenKind};
+
+    #[test]
+    fn kinds_and_spans() {
+        let tokens = lex("Game 12: 3 blue,4red");
+        let kinds: Vec<(TokenKind, &str)> = tokens.iter().map(|t| (t.kind, t.text)).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (TokenKind::Ident, "Game"),
+                (TokenKind::Int, "12"),
+                (TokenKind::Punct(':'), ":"),
+                (TokenKind::Int, "3"),
+                (TokenKind::Ident, "blue"),
+                (TokenKind::Punct(','), ","),
+                (TokenKind::Int, "4"),
+                (TokenKind::Ident, "red"),
+            ]
+        );
+        assert_eq!(tokens[1].span, Span { start: 5, end: 7 });
+        assert_eq!(tokens[7].column(), 18);
+    }
+
+    #[test]
+    fn other_formats() {
+        // d4 and d5 lines
+        let texts: Vec<&str> = lex("Card 1: 41 48 | 83 86")
+            .iter()
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(texts, vec!["Card", "1", ":", "41", "48", "|", "83", "86"]);
+
+        let tokens = lex("seed-to-soil map:");
+        assert_eq!(tokens.len(), 7);
+        assert!(tokens[1].is_punct('-'));
+    }
+
+    #[test]
+    fn unicode() {
+        let tokens = lex("жёлтый 5");
+        assert_eq!(tokens[0].kind, TokenKind::Ident);
+        assert_eq!(tokens[0].chars, Span { start: 0, end: 6 });
+        assert_eq!(tokens[1].span, Span { start: 13, end: 14 });
+        assert_eq!(tokens[1].chars, Span { start: 7, end: 8 });
+        assert_eq!(tokens[1].column(), 8);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
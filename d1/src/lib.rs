@@ -1,34 +1,185 @@
-pub mod solution1 {
-    fn calibration_value(line: &str) -> (i32, i32) {
-        let chars: Vec<char> = line.to_string().chars().collect();
+pub mod digits {
+    /// Zeros of every Unicode decimal digit (Nd) run, Unicode 14.0.
+    /// Each run is ten code points, from zero to nine.
+    const UNICODE_ZEROS: [u32; 66] = [
+        0x30, 0x660, 0x6F0, 0x7C0, 0x966, 0x9E6, 0xA66, 0xAE6, 0xB66, 0xBE6, 0xC66, 0xCE6, 0xD66,
+        0xDE6, 0xE50, 0xED0, 0xF20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80, 0x1A90,
+        0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+        0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450, 0x114D0,
+        0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50, 0x11DA0, 0x16A60, 0x16AC0,
+        0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E950, 0x1FBF0,
+    ];
 
-        let mut i = 0;
-        let mut j = chars.len() - 1;
+    /// Which characters count as digits
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum DigitPolicy {
+        /// Only `0`..`9`
+        #[default]
+        Ascii,
+        /// Any Unicode decimal digit, e.g. Arabic-Indic `٣` is 3.
+        /// Superscripts like `²` and fractions like `½` are not decimal digits.
+        Unicode,
+    }
+
+    impl DigitPolicy {
+        pub fn value(&self, c: char) -> Option<i32> {
+            match self {
+                DigitPolicy::Ascii => c.to_digit(10).map(|d| d as i32),
+                DigitPolicy::Unicode => {
+                    let code = c as u32;
+                    for zero in UNICODE_ZEROS {
+                        if code >= zero && code < zero + 10 {
+                            return Some((code - zero) as i32);
+                        }
+                    }
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_input {
+    /// Input lines for the tests
+    pub fn input(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|&s| s.into()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests_digits {
+    use super::*;
+    use digits::DigitPolicy;
+
+    #[test]
+    fn ascii() {
+        assert_eq!(DigitPolicy::Ascii.value('7'), Some(7));
+        assert_eq!(DigitPolicy::Ascii.value('٣'), None);
+        assert_eq!(DigitPolicy::Ascii.value('²'), None);
+        assert_eq!(DigitPolicy::Ascii.value('a'), None);
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(DigitPolicy::Unicode.value('7'), Some(7));
+        assert_eq!(DigitPolicy::Unicode.value('٣'), Some(3));
+        assert_eq!(DigitPolicy::Unicode.value('۹'), Some(9));
+        assert_eq!(DigitPolicy::Unicode.value('७'), Some(7));
+        assert_eq!(DigitPolicy::Unicode.value('０'), Some(0));
+        assert_eq!(DigitPolicy::Unicode.value('𝟡'), Some(9));
+        assert_eq!(DigitPolicy::Unicode.value('²'), None);
+        assert_eq!(DigitPolicy::Unicode.value('½'), None);
+        assert_eq!(DigitPolicy::Unicode.value('Ⅻ'), None);
+    }
+}
+
+pub mod missing {
+    use std::fmt;
+
+    /// What to do with a line without digits
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum Missing {
+        #[default]
+        Error,
+        Skip,
+        /// The line adds 0 to the sum
+        Zero,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Total {
+        pub sum: i64,
+        /// Lines without digits left out with `Missing::Skip`
+        pub skipped: usize,
+        /// Lines without digits counted as zero with `Missing::Zero`
+        pub zeroed: usize,
+    }
 
-        while !chars[i].is_numeric() {
-            i += 1
+    #[derive(Debug, PartialEq)]
+    pub struct NoDigitsError {
+        /// 1-based line number
+        pub line: usize,
+    }
+
+    impl fmt::Display for NoDigitsError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "line {} has no digits", self.line)
         }
-        while !chars[j].is_numeric() {
-            j -= 1
+    }
+
+    impl std::error::Error for NoDigitsError {}
+
+    /// Sums calibration values, None is a line without digits
+    pub fn total<I: IntoIterator<Item = Option<i64>>>(
+        values: I,
+        missing: Missing,
+    ) -> Result<Total, NoDigitsError> {
+        let mut total = Total {
+            sum: 0,
+            skipped: 0,
+            zeroed: 0,
+        };
+
+        for (i, value) in values.into_iter().enumerate() {
+            match value {
+                Some(v) => total.sum += v,
+                None => match missing {
+                    Missing::Error => return Err(NoDigitsError { line: i + 1 }),
+                    Missing::Skip => total.skipped += 1,
+                    Missing::Zero => total.zeroed += 1,
+                },
+            }
         }
 
-        return (
-            chars[i].to_string().parse().unwrap(),
-            chars[j].to_string().parse().unwrap(),
-        );
+        return Ok(total);
+    }
+}
+
+pub mod solution1 {
+    use crate::digits::DigitPolicy;
+    use crate::missing::{self, Missing, NoDigitsError, Total};
+
+    /// Returns None if the line has no digits
+    fn calibration_value(line: &str, policy: DigitPolicy) -> Option<(i32, i32)> {
+        let chars: Vec<char> = line.to_string().chars().collect();
+
+        let i = chars.iter().position(|c| policy.value(*c).is_some())?;
+        let j = chars.iter().rposition(|c| policy.value(*c).is_some())?;
+
+        return Some((
+            policy.value(chars[i]).unwrap(),
+            policy.value(chars[j]).unwrap(),
+        ));
     }
 
     pub fn sum_of_colibration_values(input: &Vec<String>) -> i32 {
+        return sum_of_colibration_values_with(input, DigitPolicy::Ascii);
+    }
+
+    pub fn sum_of_colibration_values_with(input: &Vec<String>, policy: DigitPolicy) -> i32 {
         let mut sum = 0;
 
         for line in input {
-            let (d1, d2) = calibration_value(&line);
+            let (d1, d2) = calibration_value(&line, policy).expect("No digits in line");
             sum += d1 * 10;
             sum += d2;
         }
 
         return sum;
     }
+
+    pub fn sum_of_colibration_values_checked(
+        input: &Vec<String>,
+        policy: DigitPolicy,
+        missing: Missing,
+    ) -> Result<Total, NoDigitsError> {
+        let values = input
+            .iter()
+            .map(|line| calibration_value(line, policy).map(|(d1, d2)| (d1 * 10 + d2) as i64));
+
+        return missing::total(values, missing);
+    }
 }
 
 #[cfg(test)]
@@ -49,198 +200,382 @@ mod tests1 {
 }
 
 pub mod solution2 {
-    use std::collections::HashMap;
-
-    struct Solution2 {
-        convert_map: HashMap<String, i32>,
-        words_forward: Vec<String>,
-        words_backward: Vec<String>,
-    }
-
-    impl Solution2 {
-        fn new() -> Solution2 {
-            let convert_map: HashMap<String, i32> = HashMap::from([
-                ("one".to_string(), 1),
-                ("two".into(), 2),
-                ("three".into(), 3),
-                ("four".into(), 4),
-                ("five".into(), 5),
-                ("six".into(), 6),
-                ("seven".into(), 7),
-                ("eight".into(), 8),
-                ("nine".into(), 9),
+    use crate::digits::DigitPolicy;
+    use crate::missing::{self, Missing, NoDigitsError, Total};
+
+    /// Words that stand for digits, e.g. "one" for 1
+    #[derive(Debug, Clone)]
+    pub struct Vocabulary {
+        words: Vec<(Vec<char>, i32)>,
+        case_insensitive: bool,
+        digits: DigitPolicy,
+    }
+
+    impl Vocabulary {
+        pub fn new(words: &[(&str, i32)]) -> Vocabulary {
+            let mut vocabulary = Vocabulary {
+                words: Vec::new(),
+                case_insensitive: false,
+                digits: DigitPolicy::Ascii,
+            };
+            for (word, value) in words {
+                vocabulary = vocabulary.add(word, *value);
+            }
+
+            return vocabulary;
+        }
+
+        pub fn english() -> Vocabulary {
+            return Vocabulary::new(&[
+                ("one", 1),
+                ("two", 2),
+                ("three", 3),
+                ("four", 4),
+                ("five", 5),
+                ("six", 6),
+                ("seven", 7),
+                ("eight", 8),
+                ("nine", 9),
             ]);
+        }
 
-            let words_forward: Vec<String> = vec![
-                "one", "two", "six", "four", "five", "nine", "three", "seven", "eight",
-            ]
-            .iter()
-            .map(|&s| s.into())
-            .collect();
+        pub fn german() -> Vocabulary {
+            return Vocabulary::new(&[
+                ("null", 0),
+                ("eins", 1),
+                ("zwei", 2),
+                ("drei", 3),
+                ("vier", 4),
+                ("fünf", 5),
+                ("sechs", 6),
+                ("sieben", 7),
+                ("acht", 8),
+                ("neun", 9),
+            ]);
+        }
 
-            let words_backward: Vec<String> = words_forward
-                .clone()
-                .iter_mut()
-                .map(|s| s.chars().rev().collect::<String>())
-                .collect();
+        pub fn russian() -> Vocabulary {
+            return Vocabulary::new(&[
+                ("ноль", 0),
+                ("один", 1),
+                ("два", 2),
+                ("три", 3),
+                ("четыре", 4),
+                ("пять", 5),
+                ("шесть", 6),
+                ("семь", 7),
+                ("восемь", 8),
+                ("девять", 9),
+            ]);
+        }
 
-            return Solution2 {
-                convert_map,
-                words_forward,
-                words_backward,
-            };
+        pub fn add(mut self, word: &str, value: i32) -> Vocabulary {
+            assert!(!word.is_empty(), "Empty word");
+            self.words.push((word.chars().collect(), value));
+            return self;
         }
 
-        /// words are sorted by length
-        /// Returns Some(index of found word) or None
-        fn try_find_any_word(
-            &self,
-            chars: &Vec<char>,
-            start_pos: usize,
-            forward: bool,
-            words: &Vec<String>,
-        ) -> Option<usize> {
-            let mut i: i32 = start_pos as i32;
-            let delta: i32 = if forward { 1 } else { -1 };
-
-            let mut candidate: String = String::new();
-            candidate.push(chars[i as usize]);
-            i += delta;
-            candidate.push(chars[i as usize]);
-
-            i += delta;
-            if i >= chars.len() as i32 || i < 0 {
-                return None;
-            }
-            candidate.push(chars[i as usize]);
+        /// Words are matched regardless of the case, so "One" and "ONE" are 1
+        pub fn case_insensitive(mut self, case_insensitive: bool) -> Vocabulary {
+            self.case_insensitive = case_insensitive;
+            return self;
+        }
 
-            let mut j = 0;
-            // Try to find match in 3-lengts words
-            while j < words.len() && words[j].len() == 3 {
-                if words[j] == candidate {
-                    return Some(j);
-                } else {
-                    j += 1;
-                }
+        /// Which characters are digits besides the words
+        pub fn digits(mut self, digits: DigitPolicy) -> Vocabulary {
+            self.digits = digits;
+            return self;
+        }
+
+        fn normalize(&self, c: char) -> char {
+            if self.case_insensitive {
+                // Take the first char only, so positions stay the same
+                return c.to_lowercase().next().unwrap();
             }
 
-            // Try to find match in 4-lengts words
+            return c;
+        }
+    }
 
-            i += delta;
+    const NONE: usize = usize::MAX;
 
-            if i >= chars.len() as i32 || i < 0 {
-                return None;
-            }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Match {
+        kind: TokenKind,
+        start_char: usize,
+        end: usize,
+        value: i32,
+    }
 
-            candidate.push(chars[i as usize]);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum TokenKind {
+        Digit,
+        Word,
+    }
 
-            while j < words.len() && words[j].len() == 4 {
-                if words[j] == candidate {
-                    return Some(j);
-                } else {
-                    j += 1;
+    /// Matched digit or word, `start..end` are byte positions in the line
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Token {
+        pub kind: TokenKind,
+        pub start: usize,
+        pub end: usize,
+        pub value: i32,
+    }
+
+    /// Tokens picked for a line and the resulting calibration value
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct LineTrace {
+        pub first: Token,
+        pub last: Token,
+        pub value: i32,
+    }
+
+    impl LineTrace {
+        /// The line with the first token in green, the last one in blue
+        /// and their overlap, as in "twone", in magenta
+        pub fn highlight(&self, line: &str) -> String {
+            let mut out = String::new();
+            let mut current = "";
+
+            for (i, c) in line.char_indices() {
+                let in_first = i >= self.first.start && i < self.first.end;
+                let in_last = i >= self.last.start && i < self.last.end;
+                let color = match (in_first, in_last) {
+                    (true, true) => "\x1b[35m",
+                    (true, false) => "\x1b[32m",
+                    (false, true) => "\x1b[34m",
+                    (false, false) => "",
+                };
+
+                if color != current {
+                    if current != "" {
+                        out.push_str("\x1b[0m");
+                    }
+                    out.push_str(color);
+                    current = color;
                 }
+                out.push(c);
             }
 
-            // Try to find match in 5-lengts words
+            if current != "" {
+                out.push_str("\x1b[0m");
+            }
+
+            return out;
+        }
+    }
+
+    /// Aho-Corasick automaton over UTF-8 bytes of the vocabulary words.
+    /// It is built once and finds the first and the last digit or word
+    /// of a line in a single pass.
+    pub struct Matcher {
+        /// Dense transitions, 256 per state, failures are already resolved
+        next: Vec<usize>,
+        /// Word ending in the state: (length in chars, value)
+        output: Vec<Option<(usize, i32)>>,
+        /// Closest state with an output on the failure chain or NONE
+        dict: Vec<usize>,
+        case_insensitive: bool,
+        digits: DigitPolicy,
+    }
 
-            i += delta;
+    impl Matcher {
+        pub fn new(vocabulary: &Vocabulary) -> Matcher {
+            let mut next: Vec<usize> = vec![NONE; 256];
+            let mut output: Vec<Option<(usize, i32)>> = vec![None];
 
-            if i >= chars.len() as i32 || i < 0 {
-                return None;
+            for (word, value) in &vocabulary.words {
+                let mut state = 0;
+                let mut buf = [0; 4];
+                for c in word {
+                    for b in vocabulary.normalize(*c).encode_utf8(&mut buf).bytes() {
+                        let i = state * 256 + b as usize;
+                        if next[i] == NONE {
+                            next[i] = output.len();
+                            next.extend([NONE; 256]);
+                            output.push(None);
+                        }
+                        state = next[i];
+                    }
+                }
+                // The first word wins if the vocabulary has duplicates
+                if output[state].is_none() {
+                    output[state] = Some((word.len(), *value));
+                }
             }
 
-            candidate.push(chars[i as usize]);
-            while j < words.len() && words[j].len() == 5 {
-                if words[j] == candidate {
-                    return Some(j);
+            // Breadth-first, so failures of shorter prefixes are ready
+            let mut fail = vec![0; output.len()];
+            let mut dict = vec![NONE; output.len()];
+            let mut queue = std::collections::VecDeque::new();
+            for b in 0..256 {
+                if next[b] == NONE {
+                    next[b] = 0;
                 } else {
-                    j += 1;
+                    queue.push_back(next[b]);
                 }
             }
 
-            return None;
-        }
+            while let Some(state) = queue.pop_front() {
+                let f = fail[state];
+                if state != 0 && f != state {
+                    dict[state] = if output[f].is_some() { f } else { dict[f] };
+                }
 
-        fn try_lookup_forward(&self, chars: &Vec<char>, start_pos: usize) -> Option<i32> {
-            if chars[start_pos].is_numeric() {
-                return Some(chars[start_pos].to_string().parse().expect("Numeric err"));
+                for b in 0..256 {
+                    let i = state * 256 + b;
+                    if next[i] == NONE {
+                        next[i] = next[f * 256 + b];
+                    } else {
+                        fail[next[i]] = next[f * 256 + b];
+                        queue.push_back(next[i]);
+                    }
+                }
             }
 
-            return match self.try_find_any_word(chars, start_pos, true, &self.words_forward) {
-                Some(ind) => Some(*self.convert_map.get(&self.words_forward[ind]).unwrap()),
-                None => None,
+            return Matcher {
+                next,
+                output,
+                dict,
+                case_insensitive: vocabulary.case_insensitive,
+                digits: vocabulary.digits,
             };
         }
 
-        fn try_lookup_backward(&self, chars: &Vec<char>, start_pos: usize) -> Option<i32> {
-            if chars[start_pos].is_numeric() {
-                return Some(chars[start_pos].to_string().parse().expect("Parse err"));
+        fn normalize(&self, c: char) -> char {
+            if self.case_insensitive {
+                return c.to_lowercase().next().unwrap();
             }
 
-            return match self.try_find_any_word(chars, start_pos, false, &self.words_backward) {
-                Some(ind) => Some(
-                    *self
-                        .convert_map
-                        .get(
-                            &self.words_backward[ind]
-                                .clone()
-                                .chars()
-                                .rev()
-                                .collect::<String>(),
-                        )
-                        .expect("Convertion err"),
-                ),
-
-                None => None,
-            };
+            return c;
         }
-    }
 
-    fn calibration_value(line: &str) -> (i32, i32) {
-        let chars: Vec<char> = line.to_string().chars().collect();
+        /// Returns the match starting first and the match ending last
+        fn scan(&self, line: &str) -> Option<(Match, Match)> {
+            let mut state = 0;
+            let mut first: Option<Match> = None;
+            let mut last: Option<Match> = None;
+            let mut buf = [0; 4];
 
-        let solution2 = Solution2::new();
+            let mut record = |m: Match| {
+                match first {
+                    Some(f) if f.start_char < m.start_char => {}
+                    Some(f) if f.start_char == m.start_char && f.end >= m.end => {}
+                    _ => first = Some(m),
+                }
+                match last {
+                    Some(l) if l.end == m.end && l.start_char <= m.start_char => {}
+                    _ => last = Some(m),
+                }
+            };
 
-        let mut i = 0;
-        let mut j = chars.len() - 1;
-        let mut d1 = 0;
-        let mut d2 = 0;
+            for (ci, (bi, c)) in line.char_indices().enumerate() {
+                let end = bi + c.len_utf8();
+                if let Some(value) = self.digits.value(c) {
+                    record(Match {
+                        kind: TokenKind::Digit,
+                        start_char: ci,
+                        end,
+                        value,
+                    });
+                }
 
-        loop {
-            match &solution2.try_lookup_forward(&chars, i) {
-                Some(v) => {
-                    d1 = *v;
-                    break;
+                for b in self.normalize(c).encode_utf8(&mut buf).bytes() {
+                    state = self.next[state * 256 + b as usize];
+                }
+
+                let mut s = if self.output[state].is_some() {
+                    state
+                } else {
+                    self.dict[state]
+                };
+                while s != NONE {
+                    let (len, value) = self.output[s].unwrap();
+                    record(Match {
+                        kind: TokenKind::Word,
+                        start_char: ci + 1 - len,
+                        end,
+                        value,
+                    });
+                    s = self.dict[s];
                 }
-                None => i += 1,
             }
+
+            return match (first, last) {
+                (Some(f), Some(l)) => Some((f, l)),
+                _ => None,
+            };
         }
 
-        loop {
-            match &solution2.try_lookup_backward(&chars, j) {
-                Some(v) => {
-                    d2 = *v;
-                    break;
+        pub fn calibration_value(&self, line: &str) -> (i32, i32) {
+            return self.try_calibration_value(line).expect("No digits in line");
+        }
+
+        /// Returns None if the line has no digits
+        pub fn try_calibration_value(&self, line: &str) -> Option<(i32, i32)> {
+            let (first, last) = self.scan(line)?;
+            return Some((first.value, last.value));
+        }
+
+        /// Returns None if the line has no digits
+        pub fn trace(&self, line: &str) -> Option<LineTrace> {
+            let (first, last) = self.scan(line)?;
+
+            let token = |m: Match| {
+                let start = line.char_indices().nth(m.start_char).unwrap().0;
+                Token {
+                    kind: m.kind,
+                    start,
+                    end: m.end,
+                    value: m.value,
                 }
-                None => j -= 1,
-            }
+            };
+
+            return Some(LineTrace {
+                first: token(first),
+                last: token(last),
+                value: first.value * 10 + last.value,
+            });
         }
+    }
 
-        return (d1, d2);
+    /// Traces every line, use an empty vocabulary to trace part 1
+    pub fn trace(input: &Vec<String>, vocabulary: &Vocabulary) -> Vec<Option<LineTrace>> {
+        let matcher = Matcher::new(vocabulary);
+        return input.iter().map(|line| matcher.trace(line)).collect();
     }
 
     pub fn sum_of_colibration_values(input: &Vec<String>) -> i32 {
+        return sum_of_colibration_values_with(input, &Vocabulary::english());
+    }
+
+    pub fn sum_of_colibration_values_with(input: &Vec<String>, vocabulary: &Vocabulary) -> i32 {
+        let matcher = Matcher::new(vocabulary);
         let mut sum = 0;
 
         for line in input {
-            let (d1, d2) = calibration_value(&line);
+            let (d1, d2) = matcher.calibration_value(&line);
             sum += d1 * 10;
             sum += d2;
         }
 
         return sum;
     }
+
+    pub fn sum_of_colibration_values_checked(
+        input: &Vec<String>,
+        vocabulary: &Vocabulary,
+        missing: Missing,
+    ) -> Result<Total, NoDigitsError> {
+        let matcher = Matcher::new(vocabulary);
+        let values = input.iter().map(|line| {
+            matcher
+                .try_calibration_value(line)
+                .map(|(d1, d2)| (d1 * 10 + d2) as i64)
+        });
+
+        return missing::total(values, missing);
+    }
 }
 
 #[cfg(test)]
@@ -267,3 +602,702 @@ mod tests2 {
         assert_eq!(281, sum);
     }
 }
+
+#[cfg(test)]
+mod tests_vocabulary {
+    use super::*;
+    use crate::test_input::input;
+    use solution2::Vocabulary;
+
+    #[test]
+    fn zero() {
+        let vocabulary = Vocabulary::english().add("zero", 0);
+        let sum =
+            solution2::sum_of_colibration_values_with(&input(&["zeroabc7", "8zero"]), &vocabulary);
+
+        assert_eq!(sum, 7 + 80);
+    }
+
+    #[test]
+    fn german() {
+        let lines = input(&["xfünfzweiy", "achtzehn", "4neun"]);
+        let sum = solution2::sum_of_colibration_values_with(&lines, &Vocabulary::german());
+
+        assert_eq!(sum, 52 + 88 + 49);
+    }
+
+    #[test]
+    fn russian() {
+        let lines = input(&["одиндва", "qвосемьдевятьw", "шесть"]);
+        let sum = solution2::sum_of_colibration_values_with(&lines, &Vocabulary::russian());
+
+        assert_eq!(sum, 12 + 89 + 66);
+    }
+
+    #[test]
+    fn long_words() {
+        let vocabulary = Vocabulary::new(&[("seventeenlong", 7), ("x", 1)]);
+        let lines = input(&["aseventeenlongb", "seventeenlongx"]);
+        let sum = solution2::sum_of_colibration_values_with(&lines, &vocabulary);
+
+        assert_eq!(sum, 77 + 71);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let lines = input(&["OneTWOthrEE", "ДваТри"]);
+
+        let english = Vocabulary::english().case_insensitive(true);
+        assert_eq!(
+            solution2::sum_of_colibration_values_with(&lines[..1].to_vec(), &english),
+            13
+        );
+
+        let russian = Vocabulary::russian().case_insensitive(true);
+        assert_eq!(
+            solution2::sum_of_colibration_values_with(&lines[1..].to_vec(), &russian),
+            23
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_matcher {
+    use super::*;
+    use solution2::{Matcher, Vocabulary};
+
+    #[test]
+    fn overlapping_words() {
+        let matcher = Matcher::new(&Vocabulary::english());
+
+        assert_eq!(matcher.calibration_value("eightwo"), (8, 2));
+        assert_eq!(matcher.calibration_value("twone"), (2, 1));
+        assert_eq!(matcher.calibration_value("oneight"), (1, 8));
+        assert_eq!(matcher.calibration_value("xtwone3four"), (2, 4));
+        assert_eq!(matcher.calibration_value("7"), (7, 7));
+    }
+
+    #[test]
+    fn word_inside_longer_word() {
+        // "bc" ends first, but "abcd" starts first
+        let matcher = Matcher::new(&Vocabulary::new(&[("abcd", 1), ("bc", 2), ("cd", 3)]));
+
+        // "cd" and "abcd" end together, the longer one is taken
+        assert_eq!(matcher.calibration_value("xabcdy"), (1, 1));
+        assert_eq!(matcher.calibration_value("xbcdy"), (2, 3));
+    }
+
+    #[test]
+    fn failure_links() {
+        // "seve" fails in the middle of "sevseven"
+        let matcher = Matcher::new(&Vocabulary::english());
+
+        assert_eq!(matcher.calibration_value("sevseven"), (7, 7));
+        assert_eq!(matcher.calibration_value("ninine"), (9, 9));
+    }
+
+    #[test]
+    fn multibyte_and_case() {
+        let matcher = Matcher::new(&Vocabulary::russian().case_insensitive(true));
+
+        assert_eq!(matcher.calibration_value("ДВАxyzТРИ"), (2, 3));
+    }
+
+    /// cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_multi_million_lines() {
+        let lines = [
+            "two1nine",
+            "eightwothree",
+            "abcone2threexyz",
+            "xtwone3four",
+            "4nineeightseven2",
+            "zoneight234",
+            "7pqrstsixteen",
+        ];
+        let input: Vec<String> = (0..3_010_000)
+            .map(|i| lines[i % lines.len()].to_string())
+            .collect();
+
+        let now = std::time::Instant::now();
+        let sum = solution2::sum_of_colibration_values(&input);
+        let elapsed = now.elapsed();
+
+        assert_eq!(sum, 281 * 430_000);
+        println!("{} lines in {:?}", input.len(), elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests_mixed_script {
+    use super::*;
+    use crate::test_input::input;
+    use digits::DigitPolicy;
+    use solution2::Vocabulary;
+
+    #[test]
+    fn solution1_policies() {
+        let lines = input(&["a٣b²c7½", "x2y½z", "۱abc۲9"]);
+
+        assert_eq!(
+            solution1::sum_of_colibration_values_with(&lines, DigitPolicy::Ascii),
+            77 + 22 + 99
+        );
+        assert_eq!(
+            solution1::sum_of_colibration_values_with(&lines, DigitPolicy::Unicode),
+            37 + 22 + 19
+        );
+    }
+
+    #[test]
+    fn solution2_policies() {
+        let lines = input(&["٣one²", "seven٤x", "२nine"]);
+
+        let ascii = Vocabulary::english();
+        assert_eq!(
+            solution2::sum_of_colibration_values_with(&lines, &ascii),
+            11 + 77 + 99
+        );
+
+        let unicode = Vocabulary::english().digits(DigitPolicy::Unicode);
+        assert_eq!(
+            solution2::sum_of_colibration_values_with(&lines, &unicode),
+            31 + 74 + 29
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_trace {
+    use super::*;
+    use solution2::{Matcher, Token, TokenKind, Vocabulary};
+
+    #[test]
+    fn overlapping_words() {
+        let matcher = Matcher::new(&Vocabulary::english());
+
+        let trace = matcher.trace("eightwo").unwrap();
+        assert_eq!(
+            trace.first,
+            Token {
+                kind: TokenKind::Word,
+                start: 0,
+                end: 5,
+                value: 8
+            }
+        );
+        assert_eq!(
+            trace.last,
+            Token {
+                kind: TokenKind::Word,
+                start: 4,
+                end: 7,
+                value: 2
+            }
+        );
+        assert_eq!(trace.value, 82);
+
+        let trace = matcher.trace("a1twone").unwrap();
+        assert_eq!(trace.first.kind, TokenKind::Digit);
+        assert_eq!((trace.first.start, trace.first.end), (1, 2));
+        assert_eq!((trace.last.start, trace.last.end), (4, 7));
+        assert_eq!(trace.value, 11);
+    }
+
+    #[test]
+    fn byte_positions() {
+        let matcher = Matcher::new(&Vocabulary::russian());
+
+        let trace = matcher.trace("жодин7").unwrap();
+        assert_eq!((trace.first.start, trace.first.end), (2, 10));
+        assert_eq!((trace.last.start, trace.last.end), (10, 11));
+    }
+
+    #[test]
+    fn part1_and_no_digits() {
+        let input = vec!["one2three4".to_string(), "none".to_string()];
+        let traces = solution2::trace(&input, &Vocabulary::new(&[]));
+
+        assert_eq!(traces[0].as_ref().unwrap().value, 24);
+        assert_eq!(traces[1], None);
+    }
+
+    #[test]
+    fn highlight() {
+        let matcher = Matcher::new(&Vocabulary::english());
+
+        let line = "xtwone";
+        assert_eq!(
+            matcher.trace(line).unwrap().highlight(line),
+            "x\x1b[32mtw\x1b[0m\x1b[35mo\x1b[0m\x1b[34mne\x1b[0m"
+        );
+
+        let line = "a7b";
+        assert_eq!(
+            matcher.trace(line).unwrap().highlight(line),
+            "a\x1b[35m7\x1b[0mb"
+        );
+    }
+}
+
+pub mod numbers {
+    use crate::missing::{self, Missing, NoDigitsError, Total};
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Part {
+        Zero,
+        Unit,
+        Teen,
+        Tens,
+        Hundred,
+        Thousand,
+    }
+
+    const WORDS: [(&str, i64, Part); 30] = [
+        ("zero", 0, Part::Zero),
+        ("one", 1, Part::Unit),
+        ("two", 2, Part::Unit),
+        ("three", 3, Part::Unit),
+        ("four", 4, Part::Unit),
+        ("five", 5, Part::Unit),
+        ("six", 6, Part::Unit),
+        ("seven", 7, Part::Unit),
+        ("eight", 8, Part::Unit),
+        ("nine", 9, Part::Unit),
+        ("ten", 10, Part::Teen),
+        ("eleven", 11, Part::Teen),
+        ("twelve", 12, Part::Teen),
+        ("thirteen", 13, Part::Teen),
+        ("fourteen", 14, Part::Teen),
+        ("fifteen", 15, Part::Teen),
+        ("sixteen", 16, Part::Teen),
+        ("seventeen", 17, Part::Teen),
+        ("eighteen", 18, Part::Teen),
+        ("nineteen", 19, Part::Teen),
+        ("twenty", 20, Part::Tens),
+        ("thirty", 30, Part::Tens),
+        ("forty", 40, Part::Tens),
+        ("fifty", 50, Part::Tens),
+        ("sixty", 60, Part::Tens),
+        ("seventy", 70, Part::Tens),
+        ("eighty", 80, Part::Tens),
+        ("ninety", 90, Part::Tens),
+        ("hundred", 100, Part::Hundred),
+        ("thousand", 1000, Part::Thousand),
+    ];
+
+    /// How the first and the last number make the calibration value
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum Combine {
+        /// 12 and 345 are 12345, the part 1 rule for single digits
+        #[default]
+        Concat,
+        Sum,
+        Max,
+    }
+
+    impl Combine {
+        /// Returns None on overflow
+        pub fn apply(&self, first: i64, last: i64) -> Option<i64> {
+            match self {
+                Combine::Concat => {
+                    let mut shift: i64 = 10;
+                    while shift <= last {
+                        shift = shift.checked_mul(10)?;
+                    }
+                    first.checked_mul(shift)?.checked_add(last)
+                }
+                Combine::Sum => first.checked_add(last),
+                Combine::Max => Some(first.max(last)),
+            }
+        }
+    }
+
+    /// A number in the line or its calibration value does not fit into i64
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Overflow;
+
+    #[derive(Debug, PartialEq)]
+    pub enum NumbersError {
+        NoDigits(NoDigitsError),
+        /// 1-based line number where the calibration value or the sum overflows
+        Overflow {
+            line: usize,
+        },
+    }
+
+    impl fmt::Display for NumbersError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                NumbersError::NoDigits(e) => e.fmt(f),
+                NumbersError::Overflow { line } => {
+                    write!(f, "line {} does not fit into i64", line)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for NumbersError {}
+
+    /// Longest number word at `pos`: (length, value, part)
+    fn word_at(bytes: &[u8], pos: usize) -> Option<(usize, i64, Part)> {
+        let mut found: Option<(usize, i64, Part)> = None;
+        for (word, value, part) in WORDS {
+            if bytes[pos..].starts_with(word.as_bytes()) {
+                match found {
+                    Some((len, _, _)) if len >= word.len() => {}
+                    _ => found = Some((word.len(), value, part)),
+                }
+            }
+        }
+
+        return found;
+    }
+
+    /// Parses the longest number phrase at `pos`, e.g. "twentyone" or
+    /// "three thousand four-hundred two" or "nineteen hundred eighty four".
+    /// Words may be separated by one space or hyphen.
+    /// Returns (value, end of the phrase)
+    fn phrase_at(bytes: &[u8], pos: usize) -> Option<(i64, usize)> {
+        let mut total = 0;
+        let mut current = 0;
+        let mut prev: Option<Part> = None;
+        let mut seen_hundred = false;
+        let mut seen_thousand = false;
+        let mut end = pos;
+
+        loop {
+            let mut p = end;
+            if prev.is_some() && p < bytes.len() && (bytes[p] == b' ' || bytes[p] == b'-') {
+                p += 1;
+            }
+
+            let (len, value, part) = match word_at(bytes, p) {
+                Some(w) => w,
+                None => break,
+            };
+
+            let allowed = match (prev, part) {
+                (None, Part::Hundred | Part::Thousand) => false,
+                (None, _) => true,
+                (Some(Part::Zero), _) | (_, Part::Zero) => false,
+                // "nineteen hundred", but not after a thousand: "two thousand nineteen hundred"
+                (Some(Part::Unit | Part::Teen | Part::Tens), Part::Hundred) => {
+                    !seen_hundred && (current < 10 || !seen_thousand)
+                }
+                (Some(_), Part::Thousand) => !seen_thousand,
+                (Some(Part::Tens), Part::Unit) => true,
+                (Some(Part::Hundred | Part::Thousand), Part::Unit | Part::Teen | Part::Tens) => {
+                    true
+                }
+                _ => false,
+            };
+            if !allowed {
+                break;
+            }
+
+            match part {
+                Part::Hundred => {
+                    current *= 100;
+                    seen_hundred = true;
+                }
+                Part::Thousand => {
+                    total += current * 1000;
+                    current = 0;
+                    seen_hundred = false;
+                    seen_thousand = true;
+                }
+                _ => current += value,
+            }
+
+            prev = Some(part);
+            end = p + len;
+        }
+
+        return match prev {
+            Some(_) => Some((total + current, end)),
+            None => None,
+        };
+    }
+
+    /// Every whole number in the line from left to right: runs of ASCII
+    /// digits and spelled-out English numbers up to the thousands
+    pub fn find_numbers(line: &str) -> Result<Vec<i64>, Overflow> {
+        let bytes = line.as_bytes();
+        let mut numbers = Vec::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if bytes[i].is_ascii_digit() {
+                let mut n: i64 = 0;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    n = n
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add((bytes[i] - b'0') as i64))
+                        .ok_or(Overflow)?;
+                    i += 1;
+                }
+                numbers.push(n);
+            } else if let Some((n, end)) = phrase_at(bytes, i) {
+                numbers.push(n);
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+
+        return Ok(numbers);
+    }
+
+    /// Returns Ok(None) if the line has no numbers
+    pub fn calibration_value(line: &str, combine: Combine) -> Result<Option<i64>, Overflow> {
+        let numbers = find_numbers(line)?;
+        let (first, last) = match (numbers.first(), numbers.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Ok(None),
+        };
+
+        return combine.apply(first, last).map(Some).ok_or(Overflow);
+    }
+
+    pub fn sum_of_colibration_values(input: &Vec<String>, combine: Combine) -> i64 {
+        let mut sum: i64 = 0;
+
+        for line in input {
+            let value = calibration_value(&line, combine)
+                .expect("Calibration value does not fit into i64")
+                .expect("No numbers in line");
+            sum = sum.checked_add(value).expect("Sum does not fit into i64");
+        }
+
+        return sum;
+    }
+
+    pub fn sum_of_colibration_values_checked(
+        input: &Vec<String>,
+        combine: Combine,
+        missing: Missing,
+    ) -> Result<Total, NumbersError> {
+        let mut values = Vec::new();
+        let mut sum: i64 = 0;
+
+        // values are collected up to the first error, so the earliest one is reported
+        for (i, line) in input.iter().enumerate() {
+            let value = calibration_value(line, combine)
+                .map_err(|_| NumbersError::Overflow { line: i + 1 })?;
+            sum = sum
+                .checked_add(value.unwrap_or(0))
+                .ok_or(NumbersError::Overflow { line: i + 1 })?;
+            values.push(value);
+            if value.is_none() && missing == Missing::Error {
+                break;
+            }
+        }
+
+        return missing::total(values, missing).map_err(NumbersError::NoDigits);
+    }
+}
+
+#[cfg(test)]
+mod tests_numbers {
+    use super::*;
+    use missing::Missing;
+    use numbers::Combine;
+
+    #[test]
+    fn digits() {
+        assert_eq!(numbers::find_numbers("ab12cd345"), Ok(vec![12, 345]));
+        assert_eq!(
+            numbers::calibration_value("ab12cd345", Combine::Concat),
+            Ok(Some(12345))
+        );
+        assert_eq!(
+            numbers::calibration_value("ab12cd345", Combine::Sum),
+            Ok(Some(357))
+        );
+        assert_eq!(
+            numbers::calibration_value("ab12cd345", Combine::Max),
+            Ok(Some(345))
+        );
+        assert_eq!(
+            numbers::calibration_value("x7y", Combine::Concat),
+            Ok(Some(77))
+        );
+        assert_eq!(
+            numbers::calibration_value("1a0", Combine::Concat),
+            Ok(Some(10))
+        );
+    }
+
+    #[test]
+    fn spelled_out() {
+        assert_eq!(numbers::find_numbers("xtwentyoney"), Ok(vec![21]));
+        assert_eq!(numbers::find_numbers("onetwo"), Ok(vec![1, 2]));
+        assert_eq!(numbers::find_numbers("seventeen"), Ok(vec![17]));
+        assert_eq!(
+            numbers::find_numbers("onehundredtwentythree4"),
+            Ok(vec![123, 4])
+        );
+        assert_eq!(
+            numbers::find_numbers("three thousand four-hundred two"),
+            Ok(vec![3402])
+        );
+        assert_eq!(
+            numbers::find_numbers("ninetynine thousand"),
+            Ok(vec![99000])
+        );
+        assert_eq!(numbers::find_numbers("hundred"), Ok(Vec::<i64>::new()));
+        assert_eq!(numbers::find_numbers("eleven hundred"), Ok(vec![1100]));
+        assert_eq!(
+            numbers::find_numbers("nineteen hundred eighty four"),
+            Ok(vec![1984])
+        );
+        assert_eq!(
+            numbers::find_numbers("twenty one hundred thousand"),
+            Ok(vec![2100000])
+        );
+        assert_eq!(
+            numbers::find_numbers("two thousand nineteen hundred"),
+            Ok(vec![2019])
+        );
+        assert_eq!(numbers::find_numbers("twenty twelve"), Ok(vec![20, 12]));
+        assert_eq!(numbers::find_numbers("zero one"), Ok(vec![0, 1]));
+    }
+
+    #[test]
+    fn sum() {
+        let input = vec![
+            "ab12cd345".to_string(),
+            "twentyone".to_string(),
+            "five hundred x 6".to_string(),
+        ];
+
+        assert_eq!(
+            numbers::sum_of_colibration_values(&input, Combine::Concat),
+            12345 + 2121 + 5006
+        );
+        assert_eq!(numbers::calibration_value("xyz", Combine::Sum), Ok(None));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            numbers::calibration_value("1000000000000000000", Combine::Concat),
+            Err(numbers::Overflow)
+        );
+        assert_eq!(
+            numbers::calibration_value("99999999999 x 99999999999", Combine::Concat),
+            Err(numbers::Overflow)
+        );
+        assert_eq!(
+            numbers::find_numbers("a99999999999999999999b"),
+            Err(numbers::Overflow)
+        );
+        assert_eq!(
+            numbers::calibration_value("9223372036854775807", Combine::Max),
+            Ok(Some(i64::MAX))
+        );
+
+        let input = vec![
+            "12".to_string(),
+            "9223372036854775807".to_string(),
+            "1".to_string(),
+        ];
+        assert_eq!(
+            numbers::sum_of_colibration_values_checked(&input, Combine::Max, Missing::Error),
+            Err(numbers::NumbersError::Overflow { line: 2 })
+        );
+        let input = vec!["".to_string(), "99999999999 x 99999999999".to_string()];
+        let error =
+            numbers::sum_of_colibration_values_checked(&input, Combine::Concat, Missing::Skip)
+                .unwrap_err();
+        assert_eq!(error.to_string(), "line 2 does not fit into i64");
+
+        // the line without digits comes first
+        assert_eq!(
+            numbers::sum_of_colibration_values_checked(&input, Combine::Concat, Missing::Error),
+            Err(numbers::NumbersError::NoDigits(missing::NoDigitsError {
+                line: 1
+            }))
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_missing {
+    use super::*;
+    use crate::test_input::input;
+    use digits::DigitPolicy;
+    use missing::{Missing, NoDigitsError, Total};
+    use numbers::Combine;
+    use solution2::Vocabulary;
+
+    #[test]
+    fn solution1_policies() {
+        let input = input(&["1abc2", "", "xyz", "treb7uchet"]);
+
+        assert_eq!(
+            solution1::sum_of_colibration_values_checked(
+                &input,
+                DigitPolicy::Ascii,
+                Missing::Error
+            ),
+            Err(NoDigitsError { line: 2 })
+        );
+        assert_eq!(
+            solution1::sum_of_colibration_values_checked(&input, DigitPolicy::Ascii, Missing::Skip),
+            Ok(Total {
+                sum: 89,
+                skipped: 2,
+                zeroed: 0
+            })
+        );
+        assert_eq!(
+            solution1::sum_of_colibration_values_checked(&input, DigitPolicy::Ascii, Missing::Zero),
+            Ok(Total {
+                sum: 89,
+                skipped: 0,
+                zeroed: 2
+            })
+        );
+    }
+
+    #[test]
+    fn solution2_policies() {
+        let input = input(&["one", "xyz", ""]);
+        let vocabulary = Vocabulary::english();
+
+        assert_eq!(
+            solution2::sum_of_colibration_values_checked(&input, &vocabulary, Missing::Error),
+            Err(NoDigitsError { line: 2 })
+        );
+        assert_eq!(
+            solution2::sum_of_colibration_values_checked(&input, &vocabulary, Missing::Skip),
+            Ok(Total {
+                sum: 11,
+                skipped: 2,
+                zeroed: 0
+            })
+        );
+    }
+
+    #[test]
+    fn numbers_policies() {
+        let input = input(&["", "a12b3"]);
+
+        assert_eq!(
+            numbers::sum_of_colibration_values_checked(&input, Combine::Sum, Missing::Zero),
+            Ok(Total {
+                sum: 15,
+                skipped: 0,
+                zeroed: 1
+            })
+        );
+        assert_eq!(
+            NoDigitsError { line: 1 }.to_string(),
+            "line 1 has no digits"
+        );
+    }
+}
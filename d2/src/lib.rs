@@ -1,21 +1,162 @@
 pub mod common {
-    #[derive(Debug, PartialEq)]
+    use adv_common::lexer::{lex, Token, TokenKind};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::{Mutex, OnceLock};
+
+    /// Interned color name, cheap to copy and compare
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Color(u32);
+
+    struct Interner {
+        ids: HashMap<&'static str, u32>,
+        names: Vec<&'static str>,
+    }
+
+    fn interner() -> &'static Mutex<Interner> {
+        static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+        INTERNER.get_or_init(|| {
+            Mutex::new(Interner {
+                ids: HashMap::new(),
+                names: Vec::new(),
+            })
+        })
+    }
+
+    /// Letters, digits and `_`, starting with a letter: the names `lex` reads as one identifier
+    fn is_color_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        return match chars.next() {
+            Some(c) if c.is_alphabetic() => chars.all(|c| c.is_alphanumeric() || c == '_'),
+            _ => false,
+        };
+    }
+
+    impl Color {
+        pub fn intern(name: &str) -> Color {
+            assert!(is_color_name(name), "Invalid color name: {:?}", name);
+
+            let mut interner = interner().lock().unwrap();
+            if let Some(id) = interner.ids.get(name) {
+                return Color(*id);
+            }
+
+            // Names live as long as the program, there are only a few of them
+            let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+            let id = interner.names.len() as u32;
+            interner.names.push(name);
+            interner.ids.insert(name, id);
+
+            return Color(id);
+        }
+
+        pub fn name(&self) -> &'static str {
+            return interner().lock().unwrap().names[self.0 as usize];
+        }
+    }
+
+    impl fmt::Debug for Color {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+
+    /// Cubes by color, in the order the colors were added
+    #[derive(Debug, Clone, Default)]
     pub struct Bubles {
-        pub r: usize,
-        pub g: usize,
-        pub b: usize,
+        pub cubes: Vec<(Color, usize)>,
+    }
+
+    /// Sets are equal if they have the same number of every color,
+    /// the order and zero counts do not matter
+    impl PartialEq for Bubles {
+        fn eq(&self, other: &Self) -> bool {
+            for (color, _) in self.cubes.iter().chain(other.cubes.iter()) {
+                if self.get(*color) != other.get(*color) {
+                    return false;
+                }
+            }
+
+            return true;
+        }
     }
 
     impl Bubles {
         pub fn new(r: usize, g: usize, b: usize) -> Bubles {
-            return Bubles { r, g, b };
+            return Bubles::from(&[("red", r), ("green", g), ("blue", b)]);
+        }
+
+        pub fn from(cubes: &[(&str, usize)]) -> Bubles {
+            let mut bubles = Bubles::default();
+            for (name, count) in cubes {
+                bubles.add(Color::intern(name), *count);
+            }
+
+            return bubles;
+        }
+
+        pub fn get(&self, color: Color) -> usize {
+            for (c, count) in &self.cubes {
+                if *c == color {
+                    return *count;
+                }
+            }
+
+            return 0;
+        }
+
+        pub fn get_by_name(&self, name: &str) -> usize {
+            return self.get(Color::intern(name));
+        }
+
+        pub fn add(&mut self, color: Color, count: usize) {
+            for (c, n) in self.cubes.iter_mut() {
+                if *c == color {
+                    *n += count;
+                    return;
+                }
+            }
+
+            self.cubes.push((color, count));
+        }
+
+        /// Keeps the larger count of the color
+        pub fn raise(&mut self, color: Color, count: usize) {
+            if self.get(color) < count {
+                let current = self.get(color);
+                self.add(color, count - current);
+            }
+        }
+
+        /// Every color fits into `bag`, a color missing in the bag counts as 0
+        pub fn fits(&self, bag: &Bubles) -> bool {
+            for (color, count) in &self.cubes {
+                if *count > bag.get(*color) {
+                    return false;
+                }
+            }
+
+            return true;
+        }
+
+        /// Formats the set like "3 blue, 4 red", keeping the color order
+        pub fn to_line(&self) -> String {
+            let parts: Vec<String> = self
+                .cubes
+                .iter()
+                .map(|(color, count)| format!("{} {}", count, color.name()))
+                .collect();
+
+            return parts.join(", ");
         }
 
         pub fn pow(&self) -> usize {
             let mut p = 1;
-            if self.r != 0 { p *= self.r }
-            if self.g != 0 { p *= self.g }
-            if self.b != 0 { p *= self.b }
+            for (_, count) in &self.cubes {
+                if *count != 0 {
+                    p *= count
+                }
+            }
 
             return p;
         }
@@ -29,87 +170,78 @@ pub mod common {
 
     impl Game {
         pub fn find_minimum_possible_bubles(&self) -> Bubles {
-            let mut bubles = Bubles::new(0,0,0);
+            let mut bubles = Bubles::default();
             for b in &self.rounds {
-                if b.r > bubles.r {
-                    bubles.r = b.r;
-                }
-                if b.g > bubles.g {
-                    bubles.g = b.g;
-                }
-                if b.b > bubles.b {
-                    bubles.b = b.b;
+                for (color, count) in &b.cubes {
+                    bubles.raise(*color, *count);
                 }
             }
 
             return bubles;
         }
 
-        pub fn possible(&self, game: &Game) -> bool {
-            for bubles in &game.rounds {
-                if self.rounds[0].r < bubles.r
-                    || self.rounds[0].g < bubles.g
-                    || self.rounds[0].b < bubles.b
-                {
-                    return false;
-                }
-            }
-
-            return true;
-        }
-
-        pub fn lookup_buble(tokens: &Vec<String>, start_pos: usize) -> (Bubles, usize) {
-            let mut bubles = Bubles::new(0, 0, 0);
+        /// Parses "3 blue, 4 red" from `start_pos` up to `;` or the end of the line.
+        /// Returns the cubes and the position of the next token
+        pub fn lookup_buble(
+            tokens: &Vec<Token>,
+            start_pos: usize,
+        ) -> Result<(Bubles, usize), ParseError> {
+            let mut bubles = Bubles::default();
             let mut i = start_pos;
 
-            while i + 1 < tokens.len() {
-                let (first, second) = (&tokens[i], &tokens[i + 1]);
-                match first.parse::<usize>() {
-                    Ok(num) => {
-                        match second.as_ref() {
-                            "blue" => bubles.b += num,
-                            "red" => bubles.r += num,
-                            "green" => bubles.g += num,
-                            _ => panic!("Illegal state"),
-                        }
-
-                        i += 2;
-                    }
-                    Err(_) => {
-                        if first == "," {
-                            i += 1;
-                            continue;
-                        } else if first == ";" {
-                            return (bubles, i);
-                        }
+            while i < tokens.len() && !tokens[i].is_punct(';') {
+                if bubles.cubes.len() > 0 {
+                    if !tokens[i].is_punct(',') {
+                        return Err(ParseError::at(&tokens[i], "expected ',' or ';'"));
                     }
+                    i += 1;
                 }
+
+                let num = expect(tokens, i, TokenKind::Int, "expected a number")?;
+                let color = expect(tokens, i + 1, TokenKind::Ident, "expected a color")?;
+                let count: usize = num
+                    .text
+                    .parse()
+                    .map_err(|_| ParseError::at(num, "number is too big"))?;
+                bubles.add(Color::intern(color.text), count);
+                i += 2;
             }
 
-            return (bubles, i);
+            return Ok((bubles, i));
         }
 
         // Parse the line, example:
         // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
         pub fn parse(line: &str) -> Game {
-            let tokens = tokens(line);
-            assert!(tokens[0] == "Game");
-            let id: usize = tokens[1].parse().unwrap();
+            return match Self::try_parse(line) {
+                Ok(game) => game,
+                Err(e) => panic!("{}: {}", e, line),
+            };
+        }
+
+        pub fn try_parse(line: &str) -> Result<Game, ParseError> {
+            let tokens = lex(line);
+            let game = expect(&tokens, 0, TokenKind::Ident, "expected 'Game'")?;
+            if game.text != "Game" {
+                return Err(ParseError::at(game, "expected 'Game'"));
+            }
+            let id = expect(&tokens, 1, TokenKind::Int, "expected a game id")?;
+            let id: usize = id
+                .text
+                .parse()
+                .map_err(|_| ParseError::at(id, "game id is too big"))?;
+            expect(&tokens, 2, TokenKind::Punct(':'), "expected ':'")?;
 
             let mut rounds: Vec<Bubles> = Vec::new();
 
             let mut i = 2;
             while i < tokens.len() {
-                if tokens[i] == ";" || tokens[i] == ":" {
-                    let (bubles, ind) = Self::lookup_buble(&tokens, i + 1);
-                    rounds.push(bubles);
-                    i = ind;
-                } else {
-                    panic!("Unexpected input");
-                }
+                let (bubles, ind) = Self::lookup_buble(&tokens, i + 1)?;
+                rounds.push(bubles);
+                i = ind;
             }
 
-            return Game { id, rounds };
+            return Ok(Game { id, rounds });
         }
 
         pub fn new(id: usize, rounds: Vec<Bubles>) -> Game {
@@ -117,43 +249,458 @@ pub mod common {
         }
     }
 
-    pub fn tokens(s: &str) -> Vec<String> {
-        let chars: Vec<char> = s.chars().collect();
-        let mut tokens: Vec<String> = Vec::new();
-        let mut current_token = String::new();
+    impl fmt::Display for Bubles {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.to_line())
+        }
+    }
+
+    /// Prints the game in the input format, `Game::parse` reads it back
+    impl fmt::Display for Game {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let rounds: Vec<String> = self.rounds.iter().map(|r| r.to_line()).collect();
+            write!(f, "Game {}: {}", self.id, rounds.join("; "))
+        }
+    }
 
-        for ch in chars {
-            match ch {
-                ' ' => {
-                    if current_token.len() > 0 {
-                        tokens.push(current_token.clone());
-                        current_token = String::new();
+    /// Cubes loaded into the bag before a game
+    #[derive(Debug, PartialEq)]
+    pub struct Bag {
+        pub cubes: Bubles,
+    }
+
+    impl Bag {
+        pub fn new(cubes: Bubles) -> Bag {
+            return Bag { cubes };
+        }
+
+        /// The smallest bag that makes all the games possible
+        pub fn smallest_for(games: &[Game]) -> Bag {
+            let mut cubes = Bubles::default();
+            for game in games {
+                for (color, count) in &game.find_minimum_possible_bubles().cubes {
+                    cubes.raise(*color, *count);
+                }
+            }
+
+            return Bag { cubes };
+        }
+
+        pub fn possible(&self, game: &Game) -> bool {
+            for bubles in &game.rounds {
+                if !bubles.fits(&self.cubes) {
+                    return false;
+                }
+            }
+
+            return true;
+        }
+
+        /// Ids of the games possible with this bag
+        pub fn possible_games(&self, games: &[Game]) -> Vec<usize> {
+            let mut ids = Vec::new();
+            for game in games {
+                if self.possible(game) {
+                    ids.push(game.id);
+                }
+            }
+
+            return ids;
+        }
+
+        /// Colors whose limit alone rules out the game, in the order they
+        /// appear in the game. Empty if the game is possible.
+        pub fn limiting_colors(&self, game: &Game) -> Vec<Color> {
+            let mut colors = Vec::new();
+            for (color, count) in &game.find_minimum_possible_bubles().cubes {
+                if *count > self.cubes.get(*color) {
+                    colors.push(*color);
+                }
+            }
+
+            return colors;
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct ParseError {
+        /// 1-based character column
+        pub column: usize,
+        pub message: &'static str,
+    }
+
+    impl ParseError {
+        fn at(token: &Token, message: &'static str) -> ParseError {
+            return ParseError {
+                column: token.column(),
+                message,
+            };
+        }
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "column {}: {}", self.column, self.message)
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    /// The token at `i` of the kind. If the line ends before it,
+    /// the error points right after the last token
+    fn expect<'a, 'b>(
+        tokens: &'b Vec<Token<'a>>,
+        i: usize,
+        kind: TokenKind,
+        message: &'static str,
+    ) -> Result<&'b Token<'a>, ParseError> {
+        return match tokens.get(i) {
+            Some(t) if t.kind == kind => Ok(t),
+            Some(t) => Err(ParseError::at(t, message)),
+            None => Err(ParseError {
+                column: tokens.last().map(|t| t.chars.end).unwrap_or(0) + 1,
+                message,
+            }),
+        };
+    }
+}
+
+pub mod inference {
+    use crate::common::{Bubles, Color, Game};
+
+    /// Prior weight of a bag, it depends only on the total number of cubes
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Prior {
+        Uniform,
+        Poisson {
+            mean: f64,
+        },
+        /// Weight ratio^total, smaller bags are more likely for ratio < 1
+        Geometric {
+            ratio: f64,
+        },
+    }
+
+    impl Prior {
+        fn ln_weight(&self, total: usize, ln_fact: &Vec<f64>) -> f64 {
+            match self {
+                Prior::Uniform => 0.0,
+                Prior::Poisson { mean } => total as f64 * mean.ln() - mean - ln_fact[total],
+                Prior::Geometric { ratio } => total as f64 * ratio.ln(),
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Interval {
+        pub color: Color,
+        pub estimate: usize,
+        pub low: usize,
+        pub high: usize,
+    }
+
+    #[derive(Debug)]
+    pub struct Estimate {
+        /// The most likely bag (maximum a posteriori, or maximum
+        /// likelihood with the uniform prior)
+        pub bag: Bubles,
+        pub ln_likelihood: f64,
+        /// Credible intervals of every color from the posterior
+        pub intervals: Vec<Interval>,
+    }
+
+    /// Infers the bag from the rounds of a game. Every round draws its cubes
+    /// without replacement and puts them back before the next round, so the
+    /// rounds are independent multivariate hypergeometric samples.
+    /// All bags up to `max_total` cubes are enumerated, so keep it small
+    /// for games with many colors.
+    pub struct Estimator {
+        pub prior: Prior,
+        pub max_total: usize,
+        /// Probability mass inside the intervals, e.g. 0.95
+        pub level: f64,
+    }
+
+    impl Estimator {
+        pub fn new(prior: Prior, max_total: usize, level: f64) -> Estimator {
+            return Estimator {
+                prior,
+                max_total,
+                level,
+            };
+        }
+
+        pub fn estimate(&self, game: &Game) -> Estimate {
+            let minimum = game.find_minimum_possible_bubles();
+            let colors: Vec<Color> = minimum.cubes.iter().map(|(c, _)| *c).collect();
+            let mins: Vec<usize> = minimum.cubes.iter().map(|(_, n)| *n).collect();
+            assert!(
+                mins.iter().sum::<usize>() <= self.max_total,
+                "max_total is smaller than the minimum bag"
+            );
+
+            let mut ln_fact = vec![0.0; self.max_total + 1];
+            for i in 1..=self.max_total {
+                ln_fact[i] = ln_fact[i - 1] + (i as f64).ln();
+            }
+
+            // Draws of every round by color, in the order of `colors`
+            let draws: Vec<Vec<usize>> = game
+                .rounds
+                .iter()
+                .map(|r| colors.iter().map(|c| r.get(*c)).collect())
+                .collect();
+
+            let mut bags: Vec<(Vec<usize>, f64, f64)> = Vec::new();
+            let mut counts = mins.clone();
+            self.enumerate(0, &mut counts, &mins, &draws, &ln_fact, &mut bags);
+
+            let mut best = 0;
+            for i in 1..bags.len() {
+                if bags[i].2 > bags[best].2 {
+                    best = i;
+                }
+            }
+
+            // Posterior marginals of every color
+            let max_ln = bags[best].2;
+            let mut marginals = vec![vec![0.0; self.max_total + 1]; colors.len()];
+            let mut norm = 0.0;
+            for (counts, _, ln_post) in &bags {
+                let w = (ln_post - max_ln).exp();
+                norm += w;
+                for c in 0..colors.len() {
+                    marginals[c][counts[c]] += w;
+                }
+            }
+
+            let tail = (1.0 - self.level) / 2.0;
+            let mut intervals = Vec::new();
+            let mut bag = Bubles::default();
+            for c in 0..colors.len() {
+                let mut low = None;
+                let mut high = self.max_total;
+                let mut cumulative = 0.0;
+                for n in 0..=self.max_total {
+                    cumulative += marginals[c][n] / norm;
+                    if low.is_none() && cumulative > tail {
+                        low = Some(n);
                     }
+                    if cumulative >= 1.0 - tail {
+                        high = n;
+                        break;
+                    }
+                }
+
+                bag.add(colors[c], bags[best].0[c]);
+                intervals.push(Interval {
+                    color: colors[c],
+                    estimate: bags[best].0[c],
+                    low: low.unwrap_or(mins[c]),
+                    high,
+                });
+            }
+
+            return Estimate {
+                bag,
+                ln_likelihood: bags[best].1,
+                intervals,
+            };
+        }
+
+        /// Collects (counts, ln likelihood, ln posterior) of every bag
+        fn enumerate(
+            &self,
+            color: usize,
+            counts: &mut Vec<usize>,
+            mins: &Vec<usize>,
+            draws: &Vec<Vec<usize>>,
+            ln_fact: &Vec<f64>,
+            bags: &mut Vec<(Vec<usize>, f64, f64)>,
+        ) {
+            if color == counts.len() {
+                let total: usize = counts.iter().sum();
+                let ln_choose = |n: usize, k: usize| ln_fact[n] - ln_fact[k] - ln_fact[n - k];
+
+                let mut ln_likelihood = 0.0;
+                for round in draws {
+                    let drawn: usize = round.iter().sum();
+                    for c in 0..counts.len() {
+                        ln_likelihood += ln_choose(counts[c], round[c]);
+                    }
+                    ln_likelihood -= ln_choose(total, drawn);
+                }
+
+                let ln_posterior = ln_likelihood + self.prior.ln_weight(total, ln_fact);
+                bags.push((counts.clone(), ln_likelihood, ln_posterior));
+                return;
+            }
+
+            // Cubes left for this color after the minimums of the next ones
+            let used: usize = counts[..color].iter().sum();
+            let reserved: usize = mins[color + 1..].iter().sum();
+            for n in mins[color]..=self.max_total - used - reserved {
+                counts[color] = n;
+                self.enumerate(color + 1, counts, mins, draws, ln_fact, bags);
+            }
+            counts[color] = mins[color];
+        }
+    }
+}
+
+pub mod stats {
+    use crate::common::{Bubles, Color, Game};
+
+    #[derive(Debug, PartialEq)]
+    pub struct GameStats {
+        pub id: usize,
+        pub rounds: usize,
+        /// Cubes drawn in all the rounds
+        pub cubes: usize,
+        /// Power of the minimum bag, as in `solution2`
+        pub power: usize,
+        /// Maximum of every color in a round, the minimum bag
+        pub max: Bubles,
+    }
+
+    impl GameStats {
+        pub fn collect(game: &Game) -> GameStats {
+            let max = game.find_minimum_possible_bubles();
+            let mut cubes = 0;
+            for round in &game.rounds {
+                for (_, count) in &round.cubes {
+                    cubes += count;
                 }
-                ':' | ',' | ';' => {
-                    if current_token.len() > 0 {
-                        tokens.push(current_token);
-                        current_token = String::new();
+            }
+
+            return GameStats {
+                id: game.id,
+                rounds: game.rounds.len(),
+                cubes,
+                power: max.pow(),
+                max,
+            };
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Stats {
+        pub games: Vec<GameStats>,
+        /// Every color in the order of the first appearance
+        pub colors: Vec<Color>,
+        pub rounds: usize,
+        pub cubes: usize,
+        /// Sum of the powers, the answer of `solution2`
+        pub power: usize,
+        /// Maximum of every color over all the games
+        pub max: Bubles,
+    }
+
+    impl Stats {
+        pub fn collect(games: &[Game]) -> Stats {
+            let mut stats = Stats {
+                games: Vec::new(),
+                colors: Vec::new(),
+                rounds: 0,
+                cubes: 0,
+                power: 0,
+                max: Bubles::default(),
+            };
+
+            for game in games {
+                let g = GameStats::collect(game);
+                stats.rounds += g.rounds;
+                stats.cubes += g.cubes;
+                stats.power += g.power;
+                for (color, count) in &g.max.cubes {
+                    if !stats.colors.contains(color) {
+                        stats.colors.push(*color);
                     }
-                    tokens.push(ch.to_string());
+                    stats.max.raise(*color, *count);
                 }
-                _ => current_token.push(ch),
+                stats.games.push(g);
             }
+
+            return stats;
         }
 
-        if current_token.len() > 0 {
-            tokens.push(current_token);
+        pub fn from_input(input: &Vec<String>) -> Stats {
+            let games: Vec<Game> = input.iter().map(|line| Game::parse(line)).collect();
+            return Self::collect(&games);
         }
 
-        return tokens;
+        /// One row per game and a final "total" row, a column per color
+        pub fn to_csv(&self) -> String {
+            let mut out = String::from("game,rounds,cubes,power");
+            for color in &self.colors {
+                out.push_str(&format!(",max_{}", color.name()));
+            }
+            out.push('\n');
+
+            let mut row = |id: String, rounds: usize, cubes: usize, power: usize, max: &Bubles| {
+                out.push_str(&format!("{},{},{},{}", id, rounds, cubes, power));
+                for color in &self.colors {
+                    out.push_str(&format!(",{}", max.get(*color)));
+                }
+                out.push('\n');
+            };
+
+            for g in &self.games {
+                row(g.id.to_string(), g.rounds, g.cubes, g.power, &g.max);
+            }
+            row(
+                "total".to_string(),
+                self.rounds,
+                self.cubes,
+                self.power,
+                &self.max,
+            );
+
+            return out;
+        }
+
+        pub fn to_json(&self) -> String {
+            let max_json = |max: &Bubles| {
+                let parts: Vec<String> = self
+                    .colors
+                    .iter()
+                    .map(|c| format!("\"{}\":{}", c.name(), max.get(*c)))
+                    .collect();
+                format!("{{{}}}", parts.join(","))
+            };
+
+            let games: Vec<String> = self
+                .games
+                .iter()
+                .map(|g| {
+                    format!(
+                        "{{\"id\":{},\"rounds\":{},\"cubes\":{},\"power\":{},\"max\":{}}}",
+                        g.id,
+                        g.rounds,
+                        g.cubes,
+                        g.power,
+                        max_json(&g.max)
+                    )
+                })
+                .collect();
+
+            return format!(
+                "{{\"games\":[{}],\"rounds\":{},\"cubes\":{},\"power\":{},\"max\":{}}}",
+                games.join(","),
+                self.rounds,
+                self.cubes,
+                self.power,
+                max_json(&self.max)
+            );
+        }
     }
 }
 
 pub mod solution1 {
-    use crate::common::{Game, Bubles};
+    use crate::common::{Bag, Bubles, Game};
 
     pub fn sum_of_possible_games_ids(input: &Vec<String>) -> usize {
-        let setup = Game::new(0, vec![Bubles::new(12, 13, 14)]);
+        let setup = Bag::new(Bubles::new(12, 13, 14));
         let mut sum = 0;
         for line in input {
             let g = Game::parse(line);
@@ -189,7 +736,11 @@ mod tests1 {
 
     #[test]
     fn tokenizer() {
-        let tokens = common::tokens("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green");
+        let tokens: Vec<&str> =
+            adv_common::lexer::lex("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green")
+                .iter()
+                .map(|t| t.text)
+                .collect();
         assert_eq!(
             tokens,
             vec![
@@ -255,3 +806,311 @@ mod tests2 {
     }
 
 }
+
+#[cfg(test)]
+mod tests_colors {
+    use super::*;
+    use common::{Bag, Bubles, Color, Game};
+
+    #[test]
+    fn interning() {
+        assert_eq!(Color::intern("yellow"), Color::intern("yellow"));
+        assert_ne!(Color::intern("yellow"), Color::intern("purple"));
+        assert_eq!(Color::intern("purple").name(), "purple");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid color name")]
+    fn invalid_name() {
+        Bubles::from(&[("light-blue", 1)]);
+    }
+
+    #[test]
+    fn any_palette() {
+        let g = Game::parse("Game 7: 2 yellow, 1 purple; 5 yellow; 3 purple, 1 red");
+
+        assert_eq!(
+            g.find_minimum_possible_bubles(),
+            Bubles::from(&[("yellow", 5), ("purple", 3), ("red", 1)])
+        );
+        assert_eq!(g.find_minimum_possible_bubles().pow(), 15);
+
+        let bag = Bag::new(Bubles::from(&[("yellow", 5), ("purple", 3)]));
+        assert!(!bag.possible(&g));
+
+        let bag = Bag::new(Bubles::from(&[("yellow", 5), ("purple", 3), ("red", 1)]));
+        assert!(bag.possible(&g));
+    }
+
+    #[test]
+    fn equality_ignores_order_and_zeros() {
+        assert_eq!(
+            Bubles::from(&[("red", 1), ("blue", 2)]),
+            Bubles::from(&[("blue", 2), ("green", 0), ("red", 1)])
+        );
+        assert_ne!(Bubles::from(&[("red", 1)]), Bubles::from(&[("red", 2)]));
+    }
+}
+
+#[cfg(test)]
+mod tests_bag {
+    use super::*;
+    use common::{Bag, Bubles, Color, Game};
+
+    fn games() -> Vec<Game> {
+        vec![
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+            "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
+            "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+        ]
+        .iter()
+        .map(|s| Game::parse(s))
+        .collect()
+    }
+
+    #[test]
+    fn possible_games() {
+        let bag = Bag::new(Bubles::new(12, 13, 14));
+        assert_eq!(bag.possible_games(&games()), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn smallest_bag() {
+        let bag = Bag::smallest_for(&games());
+        assert_eq!(bag.cubes, Bubles::new(20, 13, 15));
+        assert_eq!(bag.possible_games(&games()), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn limiting_colors() {
+        let bag = Bag::new(Bubles::new(12, 13, 14));
+        let games = games();
+
+        assert_eq!(bag.limiting_colors(&games[2]), vec![Color::intern("red")]);
+        assert_eq!(
+            bag.limiting_colors(&games[3]),
+            vec![Color::intern("red"), Color::intern("blue")]
+        );
+        assert_eq!(bag.limiting_colors(&games[0]), vec![]);
+    }
+}
+
+#[cfg(test)]
+mod tests_inference {
+    use super::*;
+    use common::{Bubles, Color, Game};
+    use inference::{Estimator, Prior};
+
+    #[test]
+    fn single_color() {
+        let g = Game::parse("Game 1: 3 red; 2 red");
+        let e = Estimator::new(Prior::Uniform, 10, 0.9).estimate(&g);
+
+        // Any red-only bag draws red, the smallest one is taken
+        assert_eq!(e.bag, Bubles::from(&[("red", 3)]));
+        assert_eq!(e.ln_likelihood, 0.0);
+        assert_eq!(e.intervals[0].low, 3);
+        assert_eq!(e.intervals[0].high, 10);
+    }
+
+    #[test]
+    fn balanced_draws() {
+        let g = Game::parse("Game 1: 2 red, 2 blue; 2 red, 2 blue; 1 red, 1 blue; 3 red, 3 blue");
+        let e = Estimator::new(Prior::Uniform, 20, 0.95).estimate(&g);
+
+        assert_eq!(
+            e.bag.get_by_name("red"),
+            e.bag.get_by_name("blue"),
+            "{:?}",
+            e.bag
+        );
+        for interval in &e.intervals {
+            assert!(interval.low <= interval.estimate && interval.estimate <= interval.high);
+            assert!(interval.low >= 3);
+        }
+    }
+
+    #[test]
+    fn skewed_draws() {
+        let g = Game::parse("Game 1: 4 red; 3 red, 1 blue; 4 red; 4 red; 3 red, 1 blue");
+        let e = Estimator::new(Prior::Uniform, 30, 0.95).estimate(&g);
+
+        assert!(e.bag.get_by_name("red") > e.bag.get_by_name("blue"));
+        assert_eq!(e.intervals[0].color, Color::intern("red"));
+        assert_eq!(e.intervals[1].color, Color::intern("blue"));
+    }
+
+    #[test]
+    fn prior_moves_total() {
+        let g = Game::parse("Game 1: 1 red, 1 blue; 2 red; 1 blue, 1 green");
+        let total = |prior: Prior| {
+            let bag = Estimator::new(prior, 40, 0.95).estimate(&g).bag;
+            bag.cubes.iter().map(|(_, n)| n).sum::<usize>()
+        };
+
+        assert!(total(Prior::Poisson { mean: 30.0 }) > total(Prior::Geometric { ratio: 0.5 }));
+    }
+}
+
+#[cfg(test)]
+mod tests_printer {
+    use super::*;
+    use common::{Bubles, Game};
+
+    #[test]
+    fn example_round_trip() {
+        let line = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
+        assert_eq!(Game::parse(line).to_string(), line);
+    }
+
+    #[test]
+    fn normalizes_spacing() {
+        let line = "Game  12 :3 blue ,4 red;1 red";
+        assert_eq!(
+            Game::parse(line).to_string(),
+            "Game 12: 3 blue, 4 red; 1 red"
+        );
+    }
+
+    /// Linear congruential generator, enough to generate games
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            return ((self.0 >> 33) % n as u64) as usize;
+        }
+    }
+
+    #[test]
+    fn generated_games_round_trip() {
+        let palette = [
+            "red", "green", "blue", "yellow", "purple", "cyan", "light_blue", "grey50", "жёлтый",
+        ];
+        let mut rng = Lcg(2023);
+
+        for id in 0..500 {
+            let mut rounds = Vec::new();
+            for _ in 0..1 + rng.below(5) {
+                // Distinct colors in random order
+                let mut colors = palette.to_vec();
+                let mut cubes = Vec::new();
+                for _ in 0..1 + rng.below(palette.len()) {
+                    let color = colors.remove(rng.below(colors.len()));
+                    cubes.push((color, rng.below(25)));
+                }
+                rounds.push(Bubles::from(&cubes));
+            }
+            let game = Game::new(id, rounds);
+
+            let line = game.to_string();
+            let parsed = Game::parse(&line);
+
+            assert_eq!(parsed.id, game.id);
+            assert_eq!(parsed.rounds.len(), game.rounds.len());
+            for (a, b) in parsed.rounds.iter().zip(game.rounds.iter()) {
+                assert_eq!(a.cubes, b.cubes, "{}", line);
+            }
+            assert_eq!(parsed.to_string(), line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_parse_errors {
+    use super::*;
+    use common::{Game, ParseError};
+
+    fn error(line: &str) -> ParseError {
+        Game::try_parse(line).unwrap_err()
+    }
+
+    #[test]
+    fn columns() {
+        assert_eq!(
+            error("Game 1: 3 blue, x red"),
+            ParseError {
+                column: 17,
+                message: "expected a number"
+            }
+        );
+        assert_eq!(error("Game 1: 3 blue 4 red").column, 16);
+        assert_eq!(error("Gme 1: 3 blue").column, 1);
+        assert_eq!(error("Game x: 3 blue").column, 6);
+        assert_eq!(error("Game 1 3 blue").column, 8);
+        assert_eq!(error("Game 1: 3 blue; 4").column, 18);
+        assert_eq!(error("").column, 1);
+        assert_eq!(error("Game 1: 99999999999999999999999 red").column, 9);
+        // characters, not bytes
+        assert_eq!(error("Game 1: 3 жёлтый 4 red").column, 18);
+        assert_eq!(error("Game 1: 3 жёлтый,").column, 18);
+        assert_eq!(
+            error("Game 1: 3 blue, x red").to_string(),
+            "column 17: expected a number"
+        );
+    }
+
+    #[test]
+    fn empty_rounds() {
+        assert_eq!(Game::try_parse("Game 1:").unwrap().rounds.len(), 1);
+        assert_eq!(Game::try_parse("Game 1: 1 red;").unwrap().rounds.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod tests_stats {
+    use super::*;
+    use common::Bubles;
+    use stats::Stats;
+
+    fn input() -> Vec<String> {
+        vec![
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+        ]
+        .iter()
+        .map(|&s| s.into())
+        .collect()
+    }
+
+    #[test]
+    fn collect() {
+        let stats = Stats::from_input(&input());
+
+        assert_eq!(stats.games[0].rounds, 3);
+        assert_eq!(stats.games[0].cubes, 18);
+        assert_eq!(stats.games[0].power, 48);
+        assert_eq!(stats.games[0].max, Bubles::new(4, 2, 6));
+        assert_eq!(stats.games[1].power, 12);
+
+        assert_eq!(stats.rounds, 6);
+        assert_eq!(stats.cubes, 31);
+        assert_eq!(stats.power, 60);
+        assert_eq!(stats.max, Bubles::new(4, 3, 6));
+    }
+
+    #[test]
+    fn export() {
+        let stats = Stats::from_input(&input());
+
+        assert_eq!(
+            stats.to_csv(),
+            "game,rounds,cubes,power,max_blue,max_red,max_green\n\
+             1,3,18,48,6,4,2\n\
+             2,3,13,12,4,1,3\n\
+             total,6,31,60,6,4,3\n"
+        );
+        assert_eq!(
+            stats.to_json(),
+            "{\"games\":[\
+             {\"id\":1,\"rounds\":3,\"cubes\":18,\"power\":48,\"max\":{\"blue\":6,\"red\":4,\"green\":2}},\
+             {\"id\":2,\"rounds\":3,\"cubes\":13,\"power\":12,\"max\":{\"blue\":4,\"red\":1,\"green\":3}}],\
+             \"rounds\":6,\"cubes\":31,\"power\":60,\"max\":{\"blue\":6,\"red\":4,\"green\":3}}"
+        );
+    }
+}
@@ -1,128 +1,416 @@
 pub mod common {
+    use std::collections::VecDeque;
+    use std::error::Error;
+    use std::fmt;
+    use std::iter::{Cloned, Fuse};
+    use std::slice::Iter;
+
+    /// A non-empty cell next to a number
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Symbol {
+        pub row: usize,
+        pub col: usize,
+        pub ch: char,
+    }
+
+    /// A number in the schematic, it takes columns `start..end` of the row
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Number {
+        pub row: usize,
+        pub start: usize,
+        pub end: usize,
+        pub value: i32,
+        pub symbols: Vec<Symbol>,
+    }
+
+    impl Number {
+        /// A number is a part number if any symbol is adjacent to it
+        pub fn is_part(&self) -> bool {
+            !self.symbols.is_empty()
+        }
+    }
+
+    /// A gear symbol with the numbers around it, whatever their count is
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Gear {
+        pub row: usize,
+        pub col: usize,
+        pub numbers: Vec<i32>,
+    }
+
+    impl Gear {
+        pub fn arity(&self) -> usize {
+            self.numbers.len()
+        }
+    }
+
+    /// How the numbers around a gear make its ratio
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Combine {
+        Product,
+        Sum,
+        Max,
+    }
+
+    impl Combine {
+        /// Returns None on overflow
+        pub fn apply(&self, numbers: &Vec<i32>) -> Option<i128> {
+            let mut numbers = numbers.iter().map(|n| *n as i128);
+            match self {
+                Combine::Product => numbers.try_fold(1i128, |acc, n| acc.checked_mul(n)),
+                Combine::Sum => numbers.try_fold(0i128, |acc, n| acc.checked_add(n)),
+                Combine::Max => Some(numbers.max().unwrap_or(0)),
+            }
+        }
+    }
+
+    /// Which cells around a cell are adjacent to it
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Neighbourhood {
+        /// 4-connected, the cells within the manhattan distance
+        VonNeumann,
+        /// 8-connected, the cells within the chebyshev distance
+        Moore,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Adjacency {
+        pub neighbourhood: Neighbourhood,
+        pub radius: usize,
+    }
+
+    impl Default for Adjacency {
+        fn default() -> Self {
+            Adjacency::eight()
+        }
+    }
+
+    impl Adjacency {
+        pub fn four() -> Self {
+            Adjacency {
+                neighbourhood: Neighbourhood::VonNeumann,
+                radius: 1,
+            }
+        }
+
+        pub fn eight() -> Self {
+            Adjacency {
+                neighbourhood: Neighbourhood::Moore,
+                radius: 1,
+            }
+        }
+
+        /// How many columns to the left and right are adjacent
+        /// in the row `offset` rows away, offset <= radius
+        pub fn reach(&self, offset: usize) -> usize {
+            match self.neighbourhood {
+                Neighbourhood::VonNeumann => self.radius - offset,
+                Neighbourhood::Moore => self.radius,
+            }
+        }
+    }
+
+    /// What the characters of a schematic mean
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Rules {
+        /// Empty cells
+        pub empty: Vec<char>,
+        /// Symbols, None means any character which is not empty,
+        /// including digits of the neighbouring numbers
+        pub symbols: Option<Vec<char>>,
+        pub gear: char,
+        /// How many adjacent numbers make a gear
+        pub gear_numbers: usize,
+        pub combine: Combine,
+        /// Both for part numbers and gears
+        pub adjacency: Adjacency,
+        /// A minus directly before the digits is the sign of the number,
+        /// unless there is a digit before the minus as well (`5-3`)
+        pub signed: bool,
+    }
+
+    impl Default for Rules {
+        fn default() -> Self {
+            Rules {
+                empty: vec!['.'],
+                symbols: None,
+                gear: '*',
+                gear_numbers: 2,
+                combine: Combine::Product,
+                adjacency: Adjacency::default(),
+                signed: false,
+            }
+        }
+    }
+
+    impl Rules {
+        pub fn is_symbol(&self, c: char) -> bool {
+            if self.empty.contains(&c) {
+                return false;
+            }
+
+            return match &self.symbols {
+                Some(symbols) => symbols.contains(&c),
+                None => true,
+            };
+        }
+
+        /// A minus of a signed number at the position, see `signed`
+        pub fn is_sign(&self, line: &Vec<char>, i: usize) -> bool {
+            self.signed
+                && line[i] == '-'
+                && is_digit_at(line, i + 1)
+                && !(i != 0 && is_digit_at(line, i - 1))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SchematicError {
+        pub row: usize,
+        pub col: usize,
+        pub message: String,
+    }
+
+    impl fmt::Display for SchematicError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "row {}, column {}: {}", self.row, self.col, self.message)
+        }
+    }
+
+    impl Error for SchematicError {}
+
+    /// Every number of the line must be made of decimal digits and fit into i32,
+    /// with its sign for signed numbers
+    pub fn check_line(row: usize, line: &str, rules: &Rules) -> Result<(), SchematicError> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let start = i;
+            if rules.is_sign(&chars, i) {
+                i += 1;
+            } else if !chars[i].is_numeric() {
+                i += 1;
+                continue;
+            }
+
+            while i < chars.len() && chars[i].is_numeric() {
+                if !chars[i].is_ascii_digit() {
+                    return Err(SchematicError {
+                        row,
+                        col: i,
+                        message: format!("'{}' is not a decimal digit", chars[i]),
+                    });
+                }
+                i += 1;
+            }
+
+            let number: String = chars[start..i].iter().collect();
+            if number.parse::<i32>().is_err() {
+                return Err(SchematicError {
+                    row,
+                    col: start,
+                    message: format!("{} does not fit into i32", number),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check(input: &Vec<String>, rules: &Rules) -> Result<(), SchematicError> {
+        for (row, line) in input.iter().enumerate() {
+            check_line(row, line, rules)?;
+        }
+
+        Ok(())
+    }
+
+    /// A window sliding over the lines of a schematic. It keeps the current
+    /// line and `radius` lines above and below it (at least one), only these
+    /// lines are kept in memory.
     #[derive(Debug)]
-    pub struct Cursor<'a> {
-        pub input: &'a Vec<String>,
-        pub top_line: Vec<char>,
-        pub current_line: Vec<char>,
-        pub bottom_line: Vec<char>,
+    pub struct Cursor<I: Iterator<Item = String>> {
+        lines: Fuse<I>,
+        /// How many lines are read from `lines`
+        read: usize,
+        /// The first malformed line, the cursor stops before it
+        error: Option<SchematicError>,
+        /// The virtual lines before the first and after the last rows are empty
+        window: VecDeque<Vec<char>>,
+        /// How many real lines are in the window below the current line
+        below: usize,
         pub current_row_ind: usize,
-        pub skip_characters: Vec<char>,
+        rules: Rules,
     }
 
-    impl Cursor<'_> {
-        pub fn init(input: &Vec<String>) -> Cursor {
-            assert!(input.len() > 1);
+    impl<'a> Cursor<Cloned<Iter<'a, String>>> {
+        pub fn init(input: &'a Vec<String>) -> Self {
+            Self::init_with(input, Rules::default())
+        }
+
+        pub fn init_with(input: &'a Vec<String>, rules: Rules) -> Self {
+            Cursor::from_lines(input.iter().cloned(), rules)
+        }
+    }
 
-            let skip_characters = vec!['.'];
+    impl<I: Iterator<Item = String>> Cursor<I> {
+        /// The lines may have different widths, the missing cells are empty.
+        /// An empty schematic is a single empty line.
+        /// Every line is checked when it is read, see `error`.
+        pub fn from_lines(lines: I, rules: Rules) -> Self {
+            let mut cursor = Cursor {
+                lines: lines.fuse(),
+                read: 0,
+                error: None,
+                window: VecDeque::new(),
+                below: 0,
+                current_row_ind: 0,
+                rules,
+            };
+
+            let depth = cursor.depth();
+            for _ in 0..depth {
+                cursor.window.push_back(Vec::new());
+            }
+            let first = cursor.next_line().unwrap_or_default();
+            cursor.window.push_back(first);
+
+            for _ in 0..depth {
+                match cursor.next_line() {
+                    Some(line) => {
+                        cursor.window.push_back(line);
+                        cursor.below += 1;
+                    }
+                    None => cursor.window.push_back(Vec::new()),
+                }
+            }
 
-            let current_row_ind = 0;
+            cursor
+        }
 
-            let bottom_line: Vec<char> = input[1].chars().collect();
-            let current_line: Vec<char> = input[0].chars().collect();
-            // fill the virtual first top line with dots
-            let top_line: Vec<char> = vec!['.'; bottom_line.len()];
+        /// None at the end of the schematic or at a malformed line
+        fn next_line(&mut self) -> Option<Vec<char>> {
+            if self.error.is_some() {
+                return None;
+            }
 
-            Cursor {
-                input,
-                top_line,
-                current_line,
-                bottom_line,
-                current_row_ind,
-                skip_characters,
+            let line = self.lines.next()?;
+            if let Err(e) = check_line(self.read, &line, &self.rules) {
+                self.error = Some(e);
+                return None;
             }
+
+            self.read += 1;
+            Some(line.chars().collect())
+        }
+
+        /// The first malformed line of the schematic. The cursor treats it
+        /// as the end of the schematic, so the sums are wrong if it is set.
+        pub fn error(&self) -> Option<&SchematicError> {
+            self.error.as_ref()
         }
 
-        fn height(&self) -> usize {
-            self.input.len()
+        fn depth(&self) -> usize {
+            self.rules.adjacency.radius.max(1)
+        }
+
+        /// The line `offset` rows below the current one, above if negative
+        fn line(&self, offset: isize) -> &Vec<char> {
+            &self.window[(self.depth() as isize + offset) as usize]
+        }
+
+        pub fn current_line(&self) -> &Vec<char> {
+            self.line(0)
         }
 
         fn width(&self) -> usize {
-            self.current_line.len()
+            self.current_line().len()
         }
 
-        // Before the call:
+        // Before the call (radius = 1):
         //
-        // ..11 <-- top_line
-        // .0.. <-- current_line (current_row_ind = 1)
-        // #... <-- bottom_line
+        // ..11 <-- line(-1)
+        // .0.. <-- line(0) (current_row_ind = 1)
+        // #... <-- line(1)
         // ..1.
         //
         // After the call:
         //
         // ..11
-        // .0.. <-- top_line
-        // #... <-- current_line (current_row_ind = 2)
-        // ..1. <-- bottom_line
+        // .0.. <-- line(-1)
+        // #... <-- line(0) (current_row_ind = 2)
+        // ..1. <-- line(1)
         //
         // Returns false if the cursor is closed (curent_line points at the last row).
         pub fn move_next(&mut self) -> bool {
             // Cursor points at the last line.
-            if self.current_row_ind == self.height() - 1 {
+            if self.below == 0 {
                 return false;
             }
 
             self.current_row_ind += 1;
-            // Should not clone here but I am not ready to fight with borrow checker yet.
-            self.top_line = self.current_line.clone();
-            self.current_line = self.bottom_line.clone();
-
-            // We are going to point at the last line.
-            if self.current_row_ind == self.height() - 1 {
-                // The last virual line.
-                self.bottom_line = vec!['.'; self.width()];
-            } else {
-                self.bottom_line = self.input[self.current_row_ind + 1].chars().collect();
+            self.window.pop_front();
+
+            match self.next_line() {
+                Some(line) => self.window.push_back(line),
+                None => {
+                    // A virual line after the last one.
+                    self.window.push_back(Vec::new());
+                    self.below -= 1;
+                }
             }
 
             true
         }
 
-        pub fn sum_current_line(&mut self) -> i32 {
-            assert!(self.current_row_ind < self.input.len());
-
+        pub fn sum_current_line(&mut self) -> i64 {
             let mut sum = 0;
-            let mut i = 0;
-            while i < self.current_line.len() {
-                if let Some((next_i, number)) = self.find_next_number(i) {
-                    i = next_i;
-                    sum += number;
-                } else {
-                    break;
+            for number in self.numbers_current_line() {
+                if number.is_part() {
+                    sum += number.value as i64;
                 }
             }
 
             sum
         }
 
-        fn find_next_number(&self, start_pos: usize) -> Option<(usize, i32)> {
-            // start_i is the first char in a number sequence.
-            let mut i = start_pos;
-            loop {
-                if let Some(num_start_i) = self.skip(i) {
-                    i = num_start_i;
-                    // num_end_i is the next char after the number sequence.
-                    if let Some((num_end_i, num)) = self.read_num(i) {
-                        let adjacents = self.look_around(num_start_i, num_end_i);
-                        for adj in adjacents {
-                            if !self.skip_characters.contains(&adj) {
-                                return Some((num_end_i, num));
-                            }
-                        }
-                        i = num_end_i;
-                    } else {
-                        break;
-                    }
-                } else {
-                    break;
-                }
+        /// Every number of the current line with the symbols around it
+        pub fn numbers_current_line(&self) -> Vec<Number> {
+            let mut result = Vec::new();
+            let mut i = 0;
+
+            // num_start_i is the first char in a number sequence.
+            while let Some(num_start_i) = self.skip(i) {
+                // num_end_i is the next char after the number sequence.
+                let (_, num_end_i, value) = match self.number_at(self.current_line(), num_start_i) {
+                    Some(n) => n,
+                    None => break,
+                };
+
+                let symbols = self
+                    .look_around(num_start_i, num_end_i)
+                    .into_iter()
+                    .filter(|s| self.rules.is_symbol(s.ch))
+                    .collect();
+
+                result.push(Number {
+                    row: self.current_row_ind,
+                    start: num_start_i,
+                    end: num_end_i,
+                    value,
+                    symbols,
+                });
+                i = num_end_i;
             }
 
-            return None;
+            result
         }
 
         fn skip(&self, start_pos: usize) -> Option<usize> {
+            let line = self.current_line();
             let mut i = start_pos;
-            while i < self.width() && !self.current_line[i].is_numeric() {
+            while i < self.width() && !line[i].is_numeric() && !self.rules.is_sign(line, i) {
                 i += 1;
             }
 
@@ -133,121 +421,111 @@ pub mod common {
             }
         }
 
-        fn read_num_bidirect(&self, point_position: usize, line: &Vec<char>) -> i32 {
-            let mut i = point_position as i32;
-            while i > -1 && line[i as usize].is_numeric() {
-                i -= 1;
-            }
-
-            return self.read_num_from(line, (i + 1) as usize).unwrap().1;
-        }
-
-        fn look_around_gear_for_numbers(&self, i: usize) -> Vec<i32> {
-            let mut result = Vec::new();
-
-            // process top line
-            // it means two numbers are possible to be connected on top
-            if !self.top_line[i].is_numeric() {
-                // top left corner
-                if i != 0 && self.top_line[i - 1].is_numeric() {
-                    result.push(self.read_num_bidirect(i - 1, &self.top_line));
-                }
-
-                // top right corner
-                if i != (self.width() - 1) && self.top_line[i + 1].is_numeric() {
-                    result.push(self.read_num_bidirect(i + 1, &self.top_line));
-                }
-            } else {
-                // only one number is posible on top
-                result.push(self.read_num_bidirect(i, &self.top_line));
+        /// The number covering the position, with its sign if any:
+        /// (the first position, the position after the number, the number)
+        fn number_at(
+            &self,
+            line: &Vec<char>,
+            point_position: usize,
+        ) -> Option<(usize, usize, i32)> {
+            let mut start = point_position;
+            if self.rules.is_sign(line, start) {
+                start += 1;
+            } else if !line[start].is_numeric() {
+                return None;
             }
 
-            // process bottom line
-            // it means two numbers are possible to be connected in bottom
-            if !self.bottom_line[i].is_numeric() {
-                // bottom left corner
-                if i != 0 && self.bottom_line[i - 1].is_numeric() {
-                    result.push(self.read_num_bidirect(i - 1, &self.bottom_line));
-                }
-
-                // bottom right corner
-                if i != (self.width() - 1) && self.bottom_line[i + 1].is_numeric() {
-                    result.push(self.read_num_bidirect(i + 1, &self.bottom_line));
-                }
-            } else {
-                // only one number is posible in bottom
-                result.push(self.read_num_bidirect(i, &self.bottom_line));
+            while start > 0 && line[start - 1].is_numeric() {
+                start -= 1;
             }
-
-            // process left and right current line
-            if i != 0 && self.current_line[i - 1].is_numeric() {
-                result.push(self.read_num_bidirect(i - 1, &self.current_line));
+            if start > 0 && self.rules.is_sign(line, start - 1) {
+                start -= 1;
             }
 
-            if i != (self.width() - 1) && self.current_line[i + 1].is_numeric() {
-                result.push(self.read_num_bidirect(i + 1, &self.current_line));
+            let mut end = start + 1;
+            while end < line.len() && line[end].is_numeric() {
+                end += 1;
             }
 
-            return result;
+            let num_str: String = line[start..end].iter().collect();
+            Some((
+                start,
+                end,
+                num_str.parse().expect("Number does not fit into i32"),
+            ))
         }
 
-        fn read_num_from(&self, line: &Vec<char>, start_pos: usize) -> Option<(usize, i32)> {
-            let mut i = start_pos;
-            let mut num_str = String::new();
+        // ..12..
+        // ...*.. <-- curent line
+        // .1...3
+        //
+        // look_around_gear_for_numbers(3) returns [12] for the 4-neighbourhood,
+        // [12] for the 8-neighbourhood and [12, 1, 3] for the 8-neighbourhood
+        // of radius 2. The lines above and below go first, then the current line.
+        fn look_around_gear_for_numbers(&self, i: usize) -> Vec<i32> {
+            let radius = self.rules.adjacency.radius as isize;
+            let mut result = Vec::new();
 
-            while i < line.len() && line[i].is_numeric() {
-                num_str.push(line[i]);
-                i += 1;
-            }
+            for offset in (-radius..0).chain(1..radius + 1).chain(0..1) {
+                let line = self.line(offset);
+                let reach = self.rules.adjacency.reach(offset.unsigned_abs());
 
-            if i == line.len() && !line[i - 1].is_numeric() {
-                None
-            } else {
-                Some((i, num_str.parse().unwrap()))
+                let mut col = i.saturating_sub(reach);
+                while col <= i + reach && col < line.len() {
+                    match self.number_at(line, col) {
+                        Some((_, end, value)) => {
+                            result.push(value);
+                            col = end;
+                        }
+                        None => col += 1,
+                    }
+                }
             }
-        }
 
-        fn read_num(&self, start_pos: usize) -> Option<(usize, i32)> {
-            self.read_num_from(&self.current_line, start_pos)
+            return result;
         }
 
         // .....
         // .321# <-- curent line
         // ...*.
         //
-        // look_around(1, 4) will return
+        // look_around(1, 4) will return the cells
         // ['.','.', '.', '.', '.', '.', '#', '.', '.', '.', '*', '.']
-        fn look_around(&self, start_pos: usize, end_pos: usize) -> Vec<char> {
-            assert!(self.bottom_line.len() == self.current_line.len());
-            assert!(self.current_line.len() == self.top_line.len());
-
+        // with their positions for the 8-neighbourhood, column by column.
+        // The cells missing in shorter rows and in the virtual lines are skipped,
+        // as well as the signs of the numbers.
+        fn look_around(&self, start_pos: usize, end_pos: usize) -> Vec<Symbol> {
+            let radius = self.rules.adjacency.radius;
             let mut result = Vec::new();
-            let mut i = start_pos;
-
-            if i != 0 {
-                result.push(self.top_line[i - 1]);
-                result.push(self.current_line[i - 1]);
-                result.push(self.bottom_line[i - 1]);
-            }
 
-            while i < end_pos {
-                result.push(self.top_line[i]);
-                result.push(self.bottom_line[i]);
-                i += 1;
-            }
+            for col in start_pos.saturating_sub(radius)..end_pos + radius {
+                for offset in -(radius as isize)..radius as isize + 1 {
+                    let reach = self.rules.adjacency.reach(offset.unsigned_abs());
+                    if col + reach < start_pos || col >= end_pos + reach {
+                        continue;
+                    }
+                    if offset == 0 && col >= start_pos && col < end_pos {
+                        continue;
+                    }
 
-            if i != self.width() {
-                result.push(self.top_line[i]);
-                result.push(self.current_line[i]);
-                result.push(self.bottom_line[i]);
+                    let line = self.line(offset);
+                    if col < line.len() && !self.rules.is_sign(line, col) {
+                        result.push(Symbol {
+                            row: (self.current_row_ind as isize + offset) as usize,
+                            col,
+                            ch: line[col],
+                        });
+                    }
+                }
             }
 
             result
         }
 
-        fn find_next_gear_numbers(&self, start_pos: usize) -> Option<(usize, i32, i32)> {
+        /// Returns the position after the next gear and the numbers around it
+        fn find_next_gear_numbers(&self, start_pos: usize) -> Option<(usize, Vec<i32>)> {
             let mut i = start_pos;
-            while i < self.width() && self.current_line[i] != '*' {
+            while i < self.width() && self.current_line()[i] != self.rules.gear {
                 i += 1;
             }
 
@@ -255,143 +533,783 @@ pub mod common {
                 return None;
             }
 
-            let numbers = self.look_around_gear_for_numbers(i);
-            if numbers.len() == 2 {
-                return Some((i + 1, numbers[0], numbers[1]));
-            } else {
-                return Some((i + 1, 0, 0));
-            }
+            return Some((i + 1, self.look_around_gear_for_numbers(i)));
         }
 
-        pub fn sum_current_line_gear(&mut self) -> i64 {
-            assert!(self.current_row_ind < self.input.len());
-
-            let mut sum = 0;
+        /// Every gear symbol of the current line, including the ones
+        /// without the required number of adjacent numbers
+        pub fn gears_current_line(&self) -> Vec<Gear> {
+            let mut result = Vec::new();
             let mut i = 0;
-            while i < self.current_line.len() {
-                if let Some((next_i, number1, number2)) = self.find_next_gear_numbers(i) {
-                    i = next_i;
-                    sum += number1 as i64 * number2 as i64;
-                } else {
-                    break;
+            while let Some((next_i, numbers)) = self.find_next_gear_numbers(i) {
+                result.push(Gear {
+                    row: self.current_row_ind,
+                    col: next_i - 1,
+                    numbers,
+                });
+                i = next_i;
+            }
+
+            result
+        }
+
+        /// Returns None on overflow
+        pub fn sum_current_line_gear(&mut self) -> Option<i128> {
+            let mut sum: i128 = 0;
+            for gear in self.gears_current_line() {
+                if gear.arity() == self.rules.gear_numbers {
+                    sum = sum.checked_add(self.rules.combine.apply(&gear.numbers)?)?;
                 }
             }
 
-            sum
+            Some(sum)
+        }
+    }
+
+    fn is_digit_at(line: &Vec<char>, i: usize) -> bool {
+        match line.get(i) {
+            Some(c) => c.is_numeric(),
+            None => false,
         }
     }
 }
 
-pub mod solution1 {
-    use crate::common;
+pub mod parts {
+    use crate::common::{Cursor, Number, Rules, SchematicError};
 
-    pub fn process(input: &Vec<String>) -> i32 {
-        let mut total_sum = 0;
-        let mut cursor = common::Cursor::init(&input);
+    /// Every number of the schematic from top to bottom, left to right,
+    /// part numbers and the rest
+    pub fn numbers(input: &Vec<String>) -> Result<Vec<Number>, SchematicError> {
+        numbers_with(input, &Rules::default())
+    }
+
+    pub fn numbers_with(input: &Vec<String>, rules: &Rules) -> Result<Vec<Number>, SchematicError> {
+        let mut result = Vec::new();
+        let mut cursor = Cursor::init_with(&input, rules.clone());
 
         loop {
-            let s = cursor.sum_current_line();
-            total_sum += s;
+            result.extend(cursor.numbers_current_line());
             if !cursor.move_next() {
                 break;
             }
         }
 
-        total_sum
+        if let Some(e) = cursor.error() {
+            return Err(e.clone());
+        }
+        Ok(result)
+    }
+
+    pub fn part_numbers(input: &Vec<String>) -> Result<Vec<Number>, SchematicError> {
+        let numbers = numbers(input)?;
+        return Ok(numbers.into_iter().filter(|n| n.is_part()).collect());
     }
 }
 
-pub mod solution2 {
-    use crate::common;
+pub mod gears {
+    use crate::common::{Cursor, Gear, Rules, SchematicError};
+    use std::collections::BTreeMap;
 
-    pub fn process(input: &Vec<String>) -> i64 {
-        let mut total_sum = 0;
-        let mut cursor = common::Cursor::init(&input);
+    /// Every gear symbol of the schematic with its adjacent numbers
+    pub fn gears_with(input: &Vec<String>, rules: &Rules) -> Result<Vec<Gear>, SchematicError> {
+        let mut result = Vec::new();
+        let mut cursor = Cursor::init_with(&input, rules.clone());
 
         loop {
-            let s = cursor.sum_current_line_gear();
-            total_sum += s;
+            result.extend(cursor.gears_current_line());
             if !cursor.move_next() {
                 break;
             }
         }
 
-        total_sum
+        if let Some(e) = cursor.error() {
+            return Err(e.clone());
+        }
+        Ok(result)
     }
-}
 
-#[cfg(test)]
-mod tests2 {
-    use super::*;
+    /// Number of gear symbols by the count of their adjacent numbers
+    pub fn arity_histogram(
+        input: &Vec<String>,
+        rules: &Rules,
+    ) -> Result<BTreeMap<usize, usize>, SchematicError> {
+        let mut histogram = BTreeMap::new();
+        for gear in gears_with(input, rules)? {
+            *histogram.entry(gear.arity()).or_insert(0) += 1;
+        }
 
-    #[test]
-    fn exmaple_2_works() {
-        let input = vec![
-            "467..114..",
-            "...*......",
-            "..35..633.",
-            "......#...",
-            "617*......",
-            ".....+.58.",
-            "..592.....",
-            "......755.",
-            "...$.*....",
-            ".664.598..",
-        ]
-        .iter()
-        .map(|&s| s.into())
-        .collect();
+        Ok(histogram)
+    }
+}
 
-        assert_eq!(467 * 35 + 755 * 598, solution2::process(&input));
+pub mod stream {
+    use crate::common::{Cursor, Rules, SchematicError};
+    use std::io::{self, BufRead};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Totals {
+        pub rows: usize,
+        /// Sum of the part numbers
+        pub parts: i64,
+        /// Sum of the gear ratios, None on overflow
+        pub gears: Option<i128>,
     }
 
-    #[test]
-    fn corner_cases() {
-        let input = vec![
-            "11.....", "11*....", ".......", "22*22..", "......3", ".....*3", ".......", "....10*",
-            ".....10", "..44...", "...*...", "....44.", "*55....", ".55..66", "......*", ".....66",
-            "77.77..", "..*...."
-        ]
-        .iter()
-        .map(|&s| s.into())
-        .collect();
+    /// Both answers in a single pass over the lines, so the schematic
+    /// is never kept in memory as a whole
+    pub fn process<I: Iterator<Item = String>>(
+        lines: I,
+        rules: &Rules,
+    ) -> Result<Totals, SchematicError> {
+        let mut rows = 0;
+        let lines = lines.inspect(|_| rows += 1);
 
-        assert_eq!(
-            11 * 11 + 22 * 22 + 3 * 3 + 10 * 10 + 44 * 44 + 55 * 55 + 66 * 66 + 77 * 77,
-            solution2::process(&input)
-        );
-    }
+        let mut parts: i64 = 0;
+        let mut gears: Option<i128> = Some(0);
+        let mut cursor = Cursor::from_lines(lines, rules.clone());
 
-    #[test]
-    fn real_input() {
-        let line1_sum = 0;
-        let line2_sum = 860 * 985;
-        let line3_sum = 0;
+        loop {
+            parts += cursor.sum_current_line();
+            gears = match (gears, cursor.sum_current_line_gear()) {
+                (Some(total), Some(s)) => total.checked_add(s),
+                _ => None,
+            };
+            if !cursor.move_next() {
+                break;
+            }
+        }
 
-        let input = vec![
-"........518..........918..-....472..172....776......207............38........................860..............274..945.....162..............",
-"....984.....%...............+..712...83..*....130..................+....*...545.............*......+.............../.727./....826......*....",
-"................490......519../...........16....%...42.822..486......214..../...............985.480..............798....................249.",
-        ]
-            .iter()
-            .map(|&s| s.into())
-            .collect();
+        if let Some(e) = cursor.error() {
+            return Err(e.clone());
+        }
+        drop(cursor);
 
-        assert_eq!(
-            line1_sum + line2_sum + line3_sum,
-            solution2::process(&input)
-        )
+        Ok(Totals { rows, parts, gears })
+    }
+
+    /// Reads the schematic from a file, a pipe or stdin line by line,
+    /// a malformed schematic is reported as `InvalidData`
+    pub fn process_reader<R: BufRead>(reader: R, rules: &Rules) -> io::Result<Totals> {
+        let mut error = None;
+        let lines = reader.lines().map_while(|line| match line {
+            Ok(line) => Some(line),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        });
+
+        let totals = process(lines, rules);
+        match error {
+            Some(e) => Err(e),
+            None => totals.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests1 {
-    use super::*;
+pub mod clusters {
+    use crate::common::{Number, Rules, SchematicError, Symbol};
+    use crate::parts;
+    use std::collections::HashMap;
+
+    /// Inclusive rows and columns
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Bounds {
+        pub top: usize,
+        pub left: usize,
+        pub bottom: usize,
+        pub right: usize,
+    }
 
-    #[test]
-    fn it_works() {
-        let input = vec![
-            "467..114..",
+    /// Numbers connected through the symbols around them. When digits are
+    /// symbols (the default rules) the touching numbers are connected too.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Cluster {
+        /// From top to bottom, left to right
+        pub numbers: Vec<Number>,
+        /// Sorted by position
+        pub symbols: Vec<Symbol>,
+        pub bounds: Bounds,
+        pub total: i64,
+    }
+
+    fn find(parent: &mut Vec<usize>, mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+
+        i
+    }
+
+    fn union(parent: &mut Vec<usize>, a: usize, b: usize) {
+        let a = find(parent, a);
+        let b = find(parent, b);
+        // the root is the first number of the cluster
+        parent[a.max(b)] = a.min(b);
+    }
+
+    /// Every number is in a cluster, a number without symbols is a cluster
+    /// of its own. Symbols far from the numbers are not in any cluster.
+    pub fn clusters(input: &Vec<String>, rules: &Rules) -> Result<Vec<Cluster>, SchematicError> {
+        let numbers = parts::numbers_with(input, rules)?;
+
+        let mut cells: HashMap<(usize, usize), usize> = HashMap::new();
+        for (i, number) in numbers.iter().enumerate() {
+            for col in number.start..number.end {
+                cells.insert((number.row, col), i);
+            }
+        }
+
+        // numbers go first, then the symbols
+        let mut parent: Vec<usize> = (0..numbers.len()).collect();
+        let mut symbols: Vec<Symbol> = Vec::new();
+        let mut symbol_nodes: HashMap<(usize, usize), usize> = HashMap::new();
+
+        for (i, number) in numbers.iter().enumerate() {
+            for symbol in &number.symbols {
+                let position = (symbol.row, symbol.col);
+                let node = match cells.get(&position) {
+                    Some(&other) => other,
+                    None => *symbol_nodes.entry(position).or_insert_with(|| {
+                        symbols.push(*symbol);
+                        parent.push(parent.len());
+                        parent.len() - 1
+                    }),
+                };
+                union(&mut parent, i, node);
+            }
+        }
+
+        let mut result: Vec<Cluster> = Vec::new();
+        let mut cluster_of_root: HashMap<usize, usize> = HashMap::new();
+
+        for (i, number) in numbers.iter().enumerate() {
+            let root = find(&mut parent, i);
+            let bounds = Bounds {
+                top: number.row,
+                left: number.start,
+                bottom: number.row,
+                right: number.end - 1,
+            };
+
+            match cluster_of_root.get(&root) {
+                Some(&c) => {
+                    let cluster = &mut result[c];
+                    cluster.bounds = cover(cluster.bounds, bounds);
+                    cluster.total += number.value as i64;
+                    cluster.numbers.push(number.clone());
+                }
+                None => {
+                    cluster_of_root.insert(root, result.len());
+                    result.push(Cluster {
+                        numbers: vec![number.clone()],
+                        symbols: Vec::new(),
+                        bounds,
+                        total: number.value as i64,
+                    });
+                }
+            }
+        }
+
+        for (j, symbol) in symbols.into_iter().enumerate() {
+            let root = find(&mut parent, numbers.len() + j);
+            let cluster = &mut result[cluster_of_root[&root]];
+            let bounds = Bounds {
+                top: symbol.row,
+                left: symbol.col,
+                bottom: symbol.row,
+                right: symbol.col,
+            };
+            cluster.bounds = cover(cluster.bounds, bounds);
+            cluster.symbols.push(symbol);
+        }
+
+        for cluster in result.iter_mut() {
+            cluster.symbols.sort_by_key(|s| (s.row, s.col));
+        }
+
+        Ok(result)
+    }
+
+    fn cover(a: Bounds, b: Bounds) -> Bounds {
+        Bounds {
+            top: a.top.min(b.top),
+            left: a.left.min(b.left),
+            bottom: a.bottom.max(b.bottom),
+            right: a.right.max(b.right),
+        }
+    }
+}
+
+pub mod editor {
+    use crate::common::{check_line, Cursor, Rules, SchematicError};
+
+    /// A sum of i128 values which may leave the i128 range on the way:
+    /// high * 2^128 + low
+    #[derive(Debug, Clone, Copy, Default)]
+    struct WideSum {
+        high: i64,
+        low: u128,
+    }
+
+    impl WideSum {
+        fn add(&mut self, x: i128) {
+            let (low, carry) = self.low.overflowing_add(x as u128);
+            self.low = low;
+            // x >> 127 is the sign extension of x, -1 or 0
+            self.high += (x >> 127) as i64 + carry as i64;
+        }
+
+        fn sub(&mut self, x: i128) {
+            let (low, borrow) = self.low.overflowing_sub(x as u128);
+            self.low = low;
+            self.high -= (x >> 127) as i64 + borrow as i64;
+        }
+
+        /// Returns None if the sum does not fit into i128
+        fn get(&self) -> Option<i128> {
+            let low = self.low as i128;
+            if self.high == (low >> 127) as i64 {
+                Some(low)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// A schematic which keeps the sums of every row and their totals, so an
+    /// edit rescans only the rows around the edited cell
+    #[derive(Debug, Clone)]
+    pub struct Schematic {
+        rows: Vec<String>,
+        rules: Rules,
+        part_sums: Vec<i64>,
+        gear_sums: Vec<Option<i128>>,
+        part_total: i64,
+        gear_total: WideSum,
+        /// Rows with a gear ratio sum which overflows i128
+        gear_overflows: usize,
+    }
+
+    impl Schematic {
+        pub fn new(input: &Vec<String>, rules: Rules) -> Result<Schematic, SchematicError> {
+            for (row, line) in input.iter().enumerate() {
+                check_line(row, line, &rules)?;
+            }
+
+            let mut schematic = Schematic {
+                rows: input.clone(),
+                rules,
+                part_sums: vec![0; input.len()],
+                gear_sums: vec![Some(0); input.len()],
+                part_total: 0,
+                gear_total: WideSum::default(),
+                gear_overflows: 0,
+            };
+            if !input.is_empty() {
+                schematic.update(0, input.len() - 1);
+            }
+
+            Ok(schematic)
+        }
+
+        pub fn rows(&self) -> &Vec<String> {
+            &self.rows
+        }
+
+        pub fn get(&self, row: usize, col: usize) -> Option<char> {
+            self.rows.get(row)?.chars().nth(col)
+        }
+
+        /// Sets the cell, a shorter row is filled with empty cells up to it,
+        /// which fails if the rules have no empty cell.
+        /// The schematic is unchanged if the edit makes a malformed number.
+        pub fn set(&mut self, row: usize, col: usize, ch: char) -> Result<(), SchematicError> {
+            if row >= self.rows.len() {
+                return Err(SchematicError {
+                    row,
+                    col,
+                    message: format!("the schematic has {} rows", self.rows.len()),
+                });
+            }
+
+            let mut line: Vec<char> = self.rows[row].chars().collect();
+            if line.len() < col {
+                let empty = match self.rules.empty.first() {
+                    Some(&c) => c,
+                    None => {
+                        return Err(SchematicError {
+                            row,
+                            col,
+                            message: format!(
+                                "the row has {} cells and no empty cell is configured",
+                                line.len()
+                            ),
+                        })
+                    }
+                };
+                line.resize(col, empty);
+            }
+            if line.len() == col {
+                line.push(ch);
+            } else {
+                line[col] = ch;
+            }
+
+            let line: String = line.into_iter().collect();
+            check_line(row, &line, &self.rules)?;
+            self.rows[row] = line;
+
+            let radius = self.rules.adjacency.radius.max(1);
+            let last = (row + radius).min(self.rows.len() - 1);
+            self.update(row.saturating_sub(radius), last);
+
+            Ok(())
+        }
+
+        pub fn part_sum(&self) -> i64 {
+            self.part_total
+        }
+
+        /// Returns None on overflow
+        pub fn gear_sum(&self) -> Option<i128> {
+            if self.gear_overflows > 0 {
+                return None;
+            }
+
+            self.gear_total.get()
+        }
+
+        /// Recomputes the sums of the rows first..=last and applies
+        /// their differences to the totals
+        fn update(&mut self, first: usize, last: usize) {
+            // the cursor needs `radius` rows around the recomputed ones
+            let radius = self.rules.adjacency.radius.max(1);
+            let from = first.saturating_sub(radius);
+            let to = (last + radius + 1).min(self.rows.len());
+
+            let lines = self.rows[from..to].iter().cloned();
+            let mut cursor = Cursor::from_lines(lines, self.rules.clone());
+            for _ in from..first {
+                cursor.move_next();
+            }
+
+            for row in first..last + 1 {
+                let part_sum = cursor.sum_current_line();
+                self.part_total += part_sum - self.part_sums[row];
+                self.part_sums[row] = part_sum;
+
+                match self.gear_sums[row] {
+                    Some(old) => self.gear_total.sub(old),
+                    None => self.gear_overflows -= 1,
+                }
+                let gear_sum = cursor.sum_current_line_gear();
+                match gear_sum {
+                    Some(new) => self.gear_total.add(new),
+                    None => self.gear_overflows += 1,
+                }
+                self.gear_sums[row] = gear_sum;
+
+                cursor.move_next();
+            }
+        }
+    }
+}
+
+pub mod render {
+    use crate::common::{Rules, SchematicError};
+    use crate::{gears, parts};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Cell {
+        Empty,
+        Part,
+        /// A digit of a number without adjacent symbols
+        NonPart,
+        Symbol,
+        /// A gear symbol with the required number of adjacent numbers
+        Gear,
+    }
+
+    impl Cell {
+        fn ansi(&self) -> &'static str {
+            match self {
+                Cell::Empty => "",
+                Cell::Part => "\x1b[32m",
+                Cell::NonPart => "\x1b[31m",
+                Cell::Symbol => "\x1b[33m",
+                Cell::Gear => "\x1b[1;35m",
+            }
+        }
+
+        fn svg(&self) -> &'static str {
+            match self {
+                Cell::Empty => "#999999",
+                Cell::Part => "#2e7d32",
+                Cell::NonPart => "#c62828",
+                Cell::Symbol => "#f9a825",
+                Cell::Gear => "#8e24aa",
+            }
+        }
+    }
+
+    /// Kind of every cell of the schematic
+    pub fn classify(input: &Vec<String>, rules: &Rules) -> Result<Vec<Vec<Cell>>, SchematicError> {
+        let mut cells: Vec<Vec<Cell>> = input
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .map(|c| {
+                        if !c.is_numeric() && rules.is_symbol(c) {
+                            Cell::Symbol
+                        } else {
+                            Cell::Empty
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for number in parts::numbers_with(input, rules)? {
+            let cell = if number.is_part() {
+                Cell::Part
+            } else {
+                Cell::NonPart
+            };
+            for col in number.start..number.end {
+                cells[number.row][col] = cell;
+            }
+        }
+
+        for gear in gears::gears_with(input, rules)? {
+            if gear.arity() == rules.gear_numbers {
+                cells[gear.row][gear.col] = Cell::Gear;
+            }
+        }
+
+        Ok(cells)
+    }
+
+    /// Runs of the same kind in the line: (kind, text)
+    fn runs(line: &str, cells: &Vec<Cell>) -> Vec<(Cell, String)> {
+        let mut result: Vec<(Cell, String)> = Vec::new();
+        for (c, cell) in line.chars().zip(cells.iter()) {
+            match result.last_mut() {
+                Some((kind, text)) if kind == cell => text.push(c),
+                _ => result.push((*cell, c.to_string())),
+            }
+        }
+
+        result
+    }
+
+    /// The schematic colored with ANSI escapes for a terminal
+    pub fn ansi(input: &Vec<String>, rules: &Rules) -> Result<String, SchematicError> {
+        let cells = classify(input, rules)?;
+        let mut out = String::new();
+
+        for (line, cells) in input.iter().zip(cells.iter()) {
+            for (cell, text) in runs(line, cells) {
+                if cell == Cell::Empty {
+                    out.push_str(&text);
+                } else {
+                    out.push_str(&format!("{}{}\x1b[0m", cell.ansi(), text));
+                }
+            }
+            out.push('\n');
+        }
+
+        Ok(out)
+    }
+
+    /// The schematic as an SVG image with a monospace font
+    pub fn svg(input: &Vec<String>, rules: &Rules) -> Result<String, SchematicError> {
+        const CELL_WIDTH: usize = 10;
+        const CELL_HEIGHT: usize = 18;
+
+        let cells = classify(input, rules)?;
+        let width = input.iter().map(|l| l.chars().count()).max().unwrap_or(0) * CELL_WIDTH;
+        let height = input.len() * CELL_HEIGHT;
+
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             font-family=\"monospace\" font-size=\"16\">\n",
+            width, height
+        );
+        out.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+        for (row, (line, cells)) in input.iter().zip(cells.iter()).enumerate() {
+            let mut col = 0;
+            for (cell, text) in runs(line, cells) {
+                let len = text.chars().count();
+                let text = text
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                out.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" fill=\"{}\" textLength=\"{}\" xml:space=\"preserve\">{}</text>\n",
+                    col * CELL_WIDTH,
+                    (row + 1) * CELL_HEIGHT - 4,
+                    cell.svg(),
+                    len * CELL_WIDTH,
+                    text
+                ));
+                col += len;
+            }
+        }
+
+        out.push_str("</svg>\n");
+        Ok(out)
+    }
+}
+
+pub mod solution1 {
+    use crate::common;
+
+    pub fn process(input: &Vec<String>) -> i32 {
+        let sum = process_with(input, &common::Rules::default())
+            .unwrap_or_else(|e| panic!("Malformed schematic: {}", e));
+        i32::try_from(sum).expect("Part number sum overflow")
+    }
+
+    pub fn process_with(
+        input: &Vec<String>,
+        rules: &common::Rules,
+    ) -> Result<i64, common::SchematicError> {
+        let mut total_sum = 0;
+        let mut cursor = common::Cursor::init_with(&input, rules.clone());
+
+        loop {
+            let s = cursor.sum_current_line();
+            total_sum += s;
+            if !cursor.move_next() {
+                break;
+            }
+        }
+
+        if let Some(e) = cursor.error() {
+            return Err(e.clone());
+        }
+        Ok(total_sum)
+    }
+}
+
+pub mod solution2 {
+    use crate::common;
+
+    pub fn process(input: &Vec<String>) -> i64 {
+        process_with(input, &common::Rules::default())
+            .unwrap_or_else(|e| panic!("Malformed schematic: {}", e))
+            .and_then(|sum| i64::try_from(sum).ok())
+            .expect("Gear ratio overflow")
+    }
+
+    /// Returns Ok(None) on overflow
+    pub fn process_with(
+        input: &Vec<String>,
+        rules: &common::Rules,
+    ) -> Result<Option<i128>, common::SchematicError> {
+        let mut total_sum: Option<i128> = Some(0);
+        let mut cursor = common::Cursor::init_with(&input, rules.clone());
+
+        loop {
+            total_sum = match (total_sum, cursor.sum_current_line_gear()) {
+                (Some(total), Some(s)) => total.checked_add(s),
+                _ => None,
+            };
+            if !cursor.move_next() {
+                break;
+            }
+        }
+
+        if let Some(e) = cursor.error() {
+            return Err(e.clone());
+        }
+        Ok(total_sum)
+    }
+}
+
+#[cfg(test)]
+mod test_input {
+    /// Input lines for the tests
+    pub fn input(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|&s| s.into()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests2 {
+    use super::*;
+
+    #[test]
+    fn exmaple_2_works() {
+        let input = vec![
+            "467..114..",
+            "...*......",
+            "..35..633.",
+            "......#...",
+            "617*......",
+            ".....+.58.",
+            "..592.....",
+            "......755.",
+            "...$.*....",
+            ".664.598..",
+        ]
+        .iter()
+        .map(|&s| s.into())
+        .collect();
+
+        assert_eq!(467 * 35 + 755 * 598, solution2::process(&input));
+    }
+
+    #[test]
+    fn corner_cases() {
+        let input = vec![
+            "11.....", "11*....", ".......", "22*22..", "......3", ".....*3", ".......", "....10*",
+            ".....10", "..44...", "...*...", "....44.", "*55....", ".55..66", "......*", ".....66",
+            "77.77..", "..*...."
+        ]
+        .iter()
+        .map(|&s| s.into())
+        .collect();
+
+        assert_eq!(
+            11 * 11 + 22 * 22 + 3 * 3 + 10 * 10 + 44 * 44 + 55 * 55 + 66 * 66 + 77 * 77,
+            solution2::process(&input)
+        );
+    }
+
+    #[test]
+    fn real_input() {
+        let line1_sum = 0;
+        let line2_sum = 860 * 985;
+        let line3_sum = 0;
+
+        let input = vec![
+"........518..........918..-....472..172....776......207............38........................860..............274..945.....162..............",
+"....984.....%...............+..712...83..*....130..................+....*...545.............*......+.............../.727./....826......*....",
+"................490......519../...........16....%...42.822..486......214..../...............985.480..............798....................249.",
+        ]
+            .iter()
+            .map(|&s| s.into())
+            .collect();
+
+        assert_eq!(
+            line1_sum + line2_sum + line3_sum,
+            solution2::process(&input)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests1 {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let input = vec![
+            "467..114..",
             "...*......",
             "..35..633.",
             "......#...",
@@ -519,3 +1437,840 @@ mod tests1 {
         )
     }
 }
+
+#[cfg(test)]
+mod tests_parts {
+    use super::*;
+    use crate::test_input::input;
+    use common::{Number, Symbol};
+
+    #[test]
+    fn positions_and_symbols() {
+        let input = input(&["467..114..", "...*......", "..35..633.", "......#..."]);
+
+        let numbers = parts::numbers(&input).unwrap();
+        assert_eq!(
+            numbers[0],
+            Number {
+                row: 0,
+                start: 0,
+                end: 3,
+                value: 467,
+                symbols: vec![Symbol {
+                    row: 1,
+                    col: 3,
+                    ch: '*'
+                }],
+            }
+        );
+        assert!(!numbers[1].is_part());
+        assert_eq!((numbers[1].start, numbers[1].end), (5, 8));
+        assert_eq!((numbers[3].row, numbers[3].value), (2, 633));
+        assert_eq!(
+            numbers[3].symbols,
+            vec![Symbol {
+                row: 3,
+                col: 6,
+                ch: '#'
+            }]
+        );
+
+        let values: Vec<i32> = parts::part_numbers(&input)
+            .unwrap()
+            .iter()
+            .map(|n| n.value)
+            .collect();
+        assert_eq!(values, vec![467, 35, 633]);
+    }
+
+    #[test]
+    fn several_symbols() {
+        let input = input(&["#.-", "+1.", "..*"]);
+
+        let numbers = parts::numbers(&input).unwrap();
+        let symbols: Vec<(usize, usize, char)> = numbers[0]
+            .symbols
+            .iter()
+            .map(|s| (s.row, s.col, s.ch))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![(0, 0, '#'), (1, 0, '+'), (0, 2, '-'), (2, 2, '*')]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_rules {
+    use super::*;
+    use crate::test_input::input;
+    use common::{Combine, Rules};
+
+    #[test]
+    fn empty_and_symbols() {
+        let schematic = input(&["1_2.3", "_#_@_", "4_5_6"]);
+
+        // '_' is a symbol by default
+        assert_eq!(solution1::process(&schematic), 21);
+
+        let rules = Rules {
+            empty: vec!['.', '_'],
+            ..Rules::default()
+        };
+        assert_eq!(
+            solution1::process_with(&schematic, &rules).unwrap(),
+            1 + 2 + 3 + 4 + 5 + 6
+        );
+
+        let rules = Rules {
+            empty: vec!['_'],
+            symbols: Some(vec!['#']),
+            ..Rules::default()
+        };
+        assert_eq!(
+            solution1::process_with(&schematic, &rules).unwrap(),
+            1 + 2 + 4 + 5
+        );
+    }
+
+    #[test]
+    fn gears() {
+        let schematic = input(&["2.3..", ".@...", "4.5.7", "...@."]);
+
+        let rules = Rules {
+            gear: '@',
+            gear_numbers: 4,
+            ..Rules::default()
+        };
+        assert_eq!(
+            solution2::process_with(&schematic, &rules).unwrap(),
+            Some(2 * 3 * 4 * 5)
+        );
+
+        let rules = Rules {
+            gear: '@',
+            gear_numbers: 4,
+            combine: Combine::Sum,
+            ..Rules::default()
+        };
+        assert_eq!(
+            solution2::process_with(&schematic, &rules).unwrap(),
+            Some(2 + 3 + 4 + 5)
+        );
+
+        let rules = Rules {
+            gear: '@',
+            gear_numbers: 2,
+            combine: Combine::Max,
+            ..Rules::default()
+        };
+        assert_eq!(
+            solution2::process_with(&schematic, &rules).unwrap(),
+            Some(7)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_gears {
+    use super::*;
+    use crate::test_input::input;
+    use common::{Combine, Rules};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn triple_gears() {
+        let schematic = input(&["1.2...", ".*..*.", "..3.4.", "*....."]);
+        let rules = Rules {
+            gear_numbers: 3,
+            ..Rules::default()
+        };
+
+        assert_eq!(
+            solution2::process_with(&schematic, &rules).unwrap(),
+            Some(6)
+        );
+        assert_eq!(solution2::process(&schematic), 0);
+    }
+
+    #[test]
+    fn histogram() {
+        let schematic = input(&["1.2...", ".*..*.", "..3.4.", "*....."]);
+        let histogram = gears::arity_histogram(&schematic, &Rules::default()).unwrap();
+
+        assert_eq!(histogram, BTreeMap::from([(0, 1), (1, 1), (3, 1)]));
+
+        let gears = gears::gears_with(&schematic, &Rules::default()).unwrap();
+        assert_eq!((gears[0].row, gears[0].col), (1, 1));
+        assert_eq!(gears[0].numbers, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn overflow() {
+        let schematic = input(&[
+            "2000000000.2000000000",
+            "2000000000*2000000000",
+            "2000000000.2000000000",
+        ]);
+        let rules = Rules {
+            gear_numbers: 6,
+            ..Rules::default()
+        };
+
+        assert_eq!(
+            Combine::Product.apply(&vec![2_000_000_000; 4]),
+            Some(16 * 10_i128.pow(36))
+        );
+        assert_eq!(solution2::process_with(&schematic, &rules).unwrap(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests_render {
+    use super::*;
+    use crate::test_input::input;
+    use common::Rules;
+    use render::Cell;
+
+    #[test]
+    fn classify() {
+        let schematic = input(&["12.3", "..*.", "4..#"]);
+        let cells = render::classify(&schematic, &Rules::default()).unwrap();
+
+        assert_eq!(
+            cells[0],
+            vec![Cell::Part, Cell::Part, Cell::Empty, Cell::Part]
+        );
+        assert_eq!(
+            cells[1],
+            vec![Cell::Empty, Cell::Empty, Cell::Gear, Cell::Empty]
+        );
+        assert_eq!(
+            cells[2],
+            vec![Cell::NonPart, Cell::Empty, Cell::Empty, Cell::Symbol]
+        );
+    }
+
+    #[test]
+    fn ansi() {
+        let schematic = input(&["1.", "*.", "..", "5#"]);
+
+        assert_eq!(
+            render::ansi(&schematic, &Rules::default()).unwrap(),
+            "\x1b[32m1\x1b[0m.\n\x1b[33m*\x1b[0m.\n..\n\x1b[32m5\x1b[0m\x1b[33m#\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn svg() {
+        let schematic = input(&["7<", ".."]);
+        let svg = render::svg(&schematic, &Rules::default()).unwrap();
+
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"36\"")
+        );
+        assert!(svg.contains("fill=\"#2e7d32\" textLength=\"10\" xml:space=\"preserve\">7</text>"));
+        assert!(svg.contains(">&lt;</text>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests_stream {
+    use super::*;
+    use crate::test_input::input;
+    use common::Rules;
+
+    const EXAMPLE: [&str; 10] = [
+        "467..114..",
+        "...*......",
+        "..35..633.",
+        "......#...",
+        "617*......",
+        ".....+.58.",
+        "..592.....",
+        "......755.",
+        "...$.*....",
+        ".664.598..",
+    ];
+
+    #[test]
+    fn example() {
+        let totals = stream::process(input(&EXAMPLE).into_iter(), &Rules::default()).unwrap();
+
+        assert_eq!(10, totals.rows);
+        assert_eq!(4361, totals.parts);
+        assert_eq!(Some(467835), totals.gears);
+    }
+
+    #[test]
+    fn reader() {
+        let text = EXAMPLE.join("\n");
+        let totals = stream::process_reader(text.as_bytes(), &Rules::default()).unwrap();
+
+        assert_eq!(4361, totals.parts);
+        assert_eq!(Some(467835), totals.gears);
+    }
+
+    #[test]
+    fn many_rows() {
+        // digits are symbols for the neighbouring numbers,
+        // so the copies are separated by an empty line
+        let copies = 10_000;
+        let height = EXAMPLE.len() + 1;
+        let lines = (0..copies * height).map(|i| match EXAMPLE.get(i % height) {
+            Some(line) => line.to_string(),
+            None => "..........".to_string(),
+        });
+        let totals = stream::process(lines, &Rules::default()).unwrap();
+
+        assert_eq!(copies * height, totals.rows);
+        assert_eq!(4361 * copies as i64, totals.parts);
+        assert_eq!(Some(467835 * copies as i128), totals.gears);
+    }
+}
+
+#[cfg(test)]
+mod tests_shapes {
+    use super::*;
+    use crate::test_input::input;
+    use common::Rules;
+
+    #[test]
+    fn single_line() {
+        let schematic = input(&["12*3..4#.5"]);
+
+        assert_eq!(19, solution1::process(&schematic));
+        assert_eq!(36, solution2::process(&schematic));
+    }
+
+    #[test]
+    fn empty() {
+        let schematic: Vec<String> = Vec::new();
+
+        assert_eq!(0, solution1::process(&schematic));
+        assert_eq!(0, solution2::process(&schematic));
+        assert!(parts::numbers(&schematic).unwrap().is_empty());
+
+        let totals = stream::process(schematic.into_iter(), &Rules::default()).unwrap();
+        assert_eq!(0, totals.rows);
+    }
+
+    #[test]
+    fn ragged() {
+        // the right neighbours of 7 and 11 exist only in the longer rows
+        let schematic = input(&["...*", "..7", "", "11", "..+.", "3"]);
+
+        let numbers = parts::numbers(&schematic).unwrap();
+        assert_eq!(
+            vec![(7, true), (11, true), (3, false)],
+            numbers
+                .iter()
+                .map(|n| (n.value, n.is_part()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(18, solution1::process(&schematic));
+
+        let schematic = input(&["5", ".*", "..6"]);
+        assert_eq!(30, solution2::process(&schematic));
+    }
+
+    #[test]
+    #[should_panic(expected = "Malformed schematic: row 0, column 0: '²' is not a decimal digit")]
+    fn malformed_panics() {
+        solution1::process(&input(&["²*"]));
+    }
+
+    #[test]
+    fn malformed() {
+        let schematic = input(&["1..", "..*", "99999999999"]);
+        let error = common::check(&schematic, &Rules::default()).unwrap_err();
+        assert_eq!((2, 0), (error.row, error.col));
+        assert_eq!(
+            "row 2, column 0: 99999999999 does not fit into i32",
+            error.to_string()
+        );
+
+        let schematic = input(&["..1½*"]);
+        let error = common::check(&schematic, &Rules::default()).unwrap_err();
+        assert_eq!((0, 3), (error.row, error.col));
+
+        let schematic = input(&["²*"]);
+        let error = solution1::process_with(&schematic, &Rules::default()).unwrap_err();
+        assert_eq!(
+            "row 0, column 0: '²' is not a decimal digit",
+            error.to_string()
+        );
+
+        let schematic = input(&["1*", "..", "99999999999"]);
+        assert_eq!(parts::numbers(&schematic).unwrap_err().row, 2);
+        assert!(gears::gears_with(&schematic, &Rules::default()).is_err());
+        assert!(render::ansi(&schematic, &Rules::default()).is_err());
+
+        let text = "1*..\n..2\n4444444444#";
+        let error = stream::process_reader(text.as_bytes(), &Rules::default()).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+    }
+}
+
+#[cfg(test)]
+mod tests_adjacency {
+    use super::*;
+    use crate::test_input::input;
+    use common::{Adjacency, Neighbourhood, Rules};
+
+    fn rules(neighbourhood: Neighbourhood, radius: usize) -> Rules {
+        Rules {
+            adjacency: Adjacency {
+                neighbourhood,
+                radius,
+            },
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn four_and_eight() {
+        let schematic = input(&["1..2", ".#.#"]);
+
+        let four = Rules {
+            adjacency: Adjacency::four(),
+            ..Rules::default()
+        };
+        assert_eq!(solution1::process_with(&schematic, &four).unwrap(), 2);
+        assert_eq!(
+            solution1::process_with(&schematic, &Rules::default()).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn radius() {
+        let schematic = input(&["5....", "..#..", "....."]);
+
+        assert_eq!(
+            solution1::process_with(&schematic, &rules(Neighbourhood::Moore, 1)).unwrap(),
+            0
+        );
+        assert_eq!(
+            solution1::process_with(&schematic, &rules(Neighbourhood::Moore, 2)).unwrap(),
+            5
+        );
+        assert_eq!(
+            solution1::process_with(&schematic, &rules(Neighbourhood::VonNeumann, 2)).unwrap(),
+            0
+        );
+        assert_eq!(
+            solution1::process_with(&schematic, &rules(Neighbourhood::VonNeumann, 3)).unwrap(),
+            5
+        );
+        assert_eq!(
+            solution1::process_with(&schematic, &rules(Neighbourhood::Moore, 0)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn gears() {
+        let schematic = input(&["..12..", "...*..", ".1...3"]);
+        let numbers = |rules: &Rules| {
+            gears::gears_with(&schematic, rules).unwrap()[0]
+                .numbers
+                .clone()
+        };
+
+        assert_eq!(numbers(&rules(Neighbourhood::VonNeumann, 1)), vec![12]);
+        assert_eq!(numbers(&rules(Neighbourhood::Moore, 1)), vec![12]);
+        assert_eq!(numbers(&rules(Neighbourhood::VonNeumann, 2)), vec![12]);
+        assert_eq!(numbers(&rules(Neighbourhood::Moore, 2)), vec![12, 1, 3]);
+
+        let rules = Rules {
+            gear_numbers: 3,
+            ..rules(Neighbourhood::Moore, 2)
+        };
+        assert_eq!(
+            solution2::process_with(&schematic, &rules).unwrap(),
+            Some(36)
+        );
+    }
+
+    #[test]
+    fn streaming_window() {
+        let schematic = input(&["7...", "....", "..$.", "....", "...", "9..#", "", "8"]);
+        let rules = rules(Neighbourhood::Moore, 2);
+
+        let totals = stream::process(schematic.clone().into_iter(), &rules).unwrap();
+        assert_eq!(totals.rows, 8);
+        // 9 and 8 are two rows apart, digits are symbols by default
+        assert_eq!(totals.parts, 7 + 9 + 8);
+        assert_eq!(
+            totals.parts,
+            solution1::process_with(&schematic, &rules).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_signed {
+    use super::*;
+    use crate::test_input::input;
+    use common::Rules;
+
+    fn signed() -> Rules {
+        Rules {
+            signed: true,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn minus_is_sign() {
+        let schematic = input(&["..-844.", "......."]);
+
+        assert_eq!(solution1::process(&schematic), 844);
+        assert_eq!(solution1::process_with(&schematic, &signed()).unwrap(), 0);
+
+        let numbers = parts::numbers_with(&schematic, &signed()).unwrap();
+        assert_eq!(
+            (2, 6, -844),
+            (numbers[0].start, numbers[0].end, numbers[0].value)
+        );
+    }
+
+    #[test]
+    fn minus_between_numbers() {
+        let schematic = input(&["-12*", "5-3."]);
+
+        // the second minus is a symbol for 5 and 3
+        assert_eq!(
+            solution1::process_with(&schematic, &signed()).unwrap(),
+            -12 + 5 + 3
+        );
+        assert_eq!(
+            gears::gears_with(&schematic, &signed()).unwrap()[0].numbers,
+            vec![3, -12]
+        );
+        assert_eq!(
+            solution2::process_with(&schematic, &signed()).unwrap(),
+            Some(-36)
+        );
+    }
+
+    #[test]
+    fn sign_is_not_symbol() {
+        let schematic = input(&["3", "-1"]);
+        let rules = Rules {
+            symbols: Some(vec!['-']),
+            ..Rules::default()
+        };
+
+        assert_eq!(solution1::process_with(&schematic, &rules).unwrap(), 3 + 1);
+        let rules = Rules {
+            signed: true,
+            ..rules
+        };
+        assert_eq!(solution1::process_with(&schematic, &rules).unwrap(), 0);
+
+        // a gear next to the sign only
+        let schematic = input(&[".*", "-5"]);
+        let rules = Rules {
+            gear_numbers: 1,
+            adjacency: common::Adjacency::four(),
+            ..signed()
+        };
+        assert_eq!(
+            gears::gears_with(&schematic, &rules).unwrap()[0].numbers,
+            vec![-5]
+        );
+    }
+
+    #[test]
+    fn i64_sums() {
+        let schematic = input(&["2000000000*", "-2000000000", "2000000000&"]);
+
+        assert_eq!(
+            solution1::process_with(&schematic, &Rules::default()).unwrap(),
+            6000000000
+        );
+        assert_eq!(
+            solution1::process_with(&schematic, &signed()).unwrap(),
+            2000000000
+        );
+    }
+
+    #[test]
+    fn i32_limits() {
+        let lines = input(&["-2147483648*", "2147483647.."]);
+
+        assert!(common::check(&lines, &Rules::default()).is_err());
+        assert_eq!(common::check(&lines, &signed()), Ok(()));
+        assert_eq!(
+            solution1::process_with(&lines, &signed()).unwrap(),
+            -2147483648 + 2147483647
+        );
+
+        let totals = stream::process(lines.clone().into_iter(), &signed()).unwrap();
+        assert_eq!(totals.parts, -1);
+
+        let mut schematic = editor::Schematic::new(&lines, signed()).unwrap();
+        assert_eq!(schematic.part_sum(), -1);
+        // 12147483648 does not fit without the sign
+        assert!(schematic.set(0, 0, '1').is_err());
+
+        let error = common::check(&input(&["-2147483649"]), &signed()).unwrap_err();
+        assert_eq!(
+            "row 0, column 0: -2147483649 does not fit into i32",
+            error.to_string()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_editor {
+    use super::*;
+    use crate::test_input::input;
+    use common::{Adjacency, Neighbourhood, Rules};
+    use editor::Schematic;
+
+    #[test]
+    fn edits() {
+        let mut schematic = Schematic::new(
+            &input(&["467..114..", "...*......", "..35..633."]),
+            Rules::default(),
+        )
+        .unwrap();
+        assert_eq!(schematic.part_sum(), 467 + 35);
+        assert_eq!(schematic.gear_sum(), Some(467 * 35));
+
+        schematic.set(1, 6, '#').unwrap();
+        assert_eq!(schematic.part_sum(), 467 + 114 + 35 + 633);
+
+        schematic.set(0, 1, '.').unwrap();
+        assert_eq!(schematic.part_sum(), 7 + 114 + 35 + 633);
+        assert_eq!(schematic.gear_sum(), Some(7 * 35));
+
+        // past the end of the row
+        schematic.set(2, 12, '*').unwrap();
+        assert_eq!(schematic.rows()[2], "..35..633...*");
+        assert_eq!(schematic.get(2, 12), Some('*'));
+    }
+
+    #[test]
+    fn errors() {
+        let mut schematic =
+            Schematic::new(&input(&["1*", "..", "999999999."]), Rules::default()).unwrap();
+
+        assert!(schematic.set(3, 0, '1').is_err());
+
+        let error = schematic.set(2, 9, '9').unwrap_err();
+        assert_eq!((2, 0), (error.row, error.col));
+        assert_eq!(schematic.rows()[2], "999999999.");
+        assert_eq!(schematic.part_sum(), 1);
+
+        assert!(Schematic::new(&input(&["99999999999"]), Rules::default()).is_err());
+
+        // no empty cell to fill a short row with
+        let rules = Rules {
+            empty: vec![],
+            ..Rules::default()
+        };
+        let mut schematic = Schematic::new(&input(&["1"]), rules).unwrap();
+        let error = schematic.set(0, 3, '.').unwrap_err();
+        assert_eq!((0, 3), (error.row, error.col));
+        assert_eq!(schematic.rows()[0], "1");
+        assert_eq!(schematic.part_sum(), 0);
+    }
+
+    #[test]
+    fn same_as_rescan() {
+        let lines = input(&[
+            "467..114..",
+            "...*......",
+            "..35..633.",
+            "......#...",
+            "617*......",
+            ".....+.58.",
+            "..592.....",
+            "......755.",
+            "...$.*....",
+            ".664.598..",
+        ]);
+        let cells = ['.', '.', '.', '*', '#', '-', '1', '7', '0'];
+        let rules = [
+            Rules::default(),
+            Rules {
+                adjacency: Adjacency {
+                    neighbourhood: Neighbourhood::VonNeumann,
+                    radius: 2,
+                },
+                signed: true,
+                ..Rules::default()
+            },
+        ];
+
+        for rules in rules {
+            let mut schematic = Schematic::new(&lines, rules.clone()).unwrap();
+            let mut seed: u64 = 7;
+            for _ in 0..300 {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let row = (seed >> 33) as usize % lines.len();
+                let col = (seed >> 40) as usize % 10;
+                let ch = cells[(seed >> 50) as usize % cells.len()];
+
+                if schematic.set(row, col, ch).is_err() {
+                    continue;
+                }
+                assert_eq!(
+                    schematic.part_sum(),
+                    solution1::process_with(schematic.rows(), &rules).unwrap()
+                );
+                assert_eq!(
+                    schematic.gear_sum(),
+                    solution2::process_with(schematic.rows(), &rules).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gear_overflow() {
+        // every block has a gear with four numbers of i32::MAX,
+        // nine of them do not fit into i128 together, eight do
+        let block = [
+            "2147483647.2147483647",
+            "..........*..........",
+            "2147483647.2147483647",
+            ".....................",
+        ];
+        let lines: Vec<String> = (0..9 * block.len())
+            .map(|i| block[i % block.len()].to_string())
+            .collect();
+        let rules = Rules {
+            gear_numbers: 4,
+            ..Rules::default()
+        };
+        let ratio = (i32::MAX as i128).pow(4);
+
+        let mut schematic = Schematic::new(&lines, rules.clone()).unwrap();
+        assert_eq!(schematic.gear_sum(), None);
+        assert_eq!(solution2::process_with(&lines, &rules).unwrap(), None);
+
+        schematic.set(1, 10, '.').unwrap();
+        assert_eq!(schematic.gear_sum(), Some(8 * ratio));
+
+        schematic.set(1, 10, '*').unwrap();
+        assert_eq!(schematic.gear_sum(), None);
+
+        // a single gear which overflows on its own
+        let rules = Rules {
+            gear_numbers: 6,
+            ..Rules::default()
+        };
+        let lines = input(&[
+            "2147483647.2147483647",
+            "2147483647*2147483647",
+            "2147483647.2147483647",
+        ]);
+        let mut schematic = Schematic::new(&lines, rules).unwrap();
+        assert_eq!(schematic.gear_sum(), None);
+        schematic.set(0, 9, '.').unwrap();
+        assert_eq!(schematic.gear_sum(), Some(0));
+    }
+}
+
+#[cfg(test)]
+mod tests_clusters {
+    use super::*;
+    use crate::test_input::input;
+    use clusters::{clusters, Bounds};
+    use common::{Adjacency, Neighbourhood, Rules};
+
+    fn values(cluster: &clusters::Cluster) -> Vec<i32> {
+        cluster.numbers.iter().map(|n| n.value).collect()
+    }
+
+    #[test]
+    fn example() {
+        let schematic = input(&[
+            "467..114..",
+            "...*......",
+            "..35..633.",
+            "......#...",
+            "617*......",
+        ]);
+        let result = clusters(&schematic, &Rules::default()).unwrap();
+
+        assert_eq!(result.len(), 4);
+        assert_eq!(values(&result[0]), vec![467, 35]);
+        assert_eq!(result[0].symbols.len(), 1);
+        assert_eq!(result[0].total, 502);
+        assert_eq!(
+            result[0].bounds,
+            Bounds {
+                top: 0,
+                left: 0,
+                bottom: 2,
+                right: 3
+            }
+        );
+
+        assert_eq!(values(&result[1]), vec![114]);
+        assert!(result[1].symbols.is_empty());
+        assert_eq!(values(&result[2]), vec![633]);
+        assert_eq!((result[2].symbols[0].row, result[2].symbols[0].col), (3, 6));
+        assert_eq!(values(&result[3]), vec![617]);
+    }
+
+    #[test]
+    fn chains() {
+        let schematic = input(&["1*2...", "...#3.", "@.....", "5....."]);
+        let result = clusters(&schematic, &Rules::default()).unwrap();
+
+        assert_eq!(values(&result[0]), vec![1, 2, 3]);
+        assert_eq!(
+            result[0].symbols.iter().map(|s| s.ch).collect::<String>(),
+            "*#"
+        );
+        assert_eq!(result[0].total, 6);
+        assert_eq!(
+            result[0].bounds,
+            Bounds {
+                top: 0,
+                left: 0,
+                bottom: 1,
+                right: 4
+            }
+        );
+        assert_eq!(values(&result[1]), vec![5]);
+        assert_eq!(result[1].symbols[0].ch, '@');
+    }
+
+    #[test]
+    fn rules() {
+        // the numbers touch each other
+        let schematic = input(&["12.", "..34"]);
+        assert_eq!(clusters(&schematic, &Rules::default()).unwrap().len(), 1);
+
+        let rules = Rules {
+            symbols: Some(vec!['#']),
+            ..Rules::default()
+        };
+        assert_eq!(clusters(&schematic, &rules).unwrap().len(), 2);
+
+        let schematic = input(&["5.#.7"]);
+        assert_eq!(clusters(&schematic, &Rules::default()).unwrap().len(), 2);
+
+        let rules = Rules {
+            adjacency: Adjacency {
+                neighbourhood: Neighbourhood::Moore,
+                radius: 2,
+            },
+            ..Rules::default()
+        };
+        let result = clusters(&schematic, &rules).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].total, 12);
+    }
+}
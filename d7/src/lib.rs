@@ -2,11 +2,31 @@ pub mod common {
     use core::cmp::Ordering;
     use std::collections::HashMap;
 
+    /// A hand that can be ranked against other hands and carries a bit.
+    pub trait Ranked: Ord {
+        fn bit(&self) -> i32;
+        fn comb_rate(&self) -> usize;
+        fn comb_name(&self) -> &'static str;
+        fn cards(&self) -> String;
+    }
+
+    pub const COMBINATION_NAMES: [&str; 7] = [
+        "high card",
+        "one pair",
+        "two pair",
+        "three of a kind",
+        "full house",
+        "four of a kind",
+        "five of a kind",
+    ];
+
     #[derive(Debug, Eq)]
     pub struct Hand {
         pub cards: Vec<char>,
         pub bit: i32,
         pub comb_rate: usize,
+        /// `J` cards are jokers: the weakest card, but they count as any other card
+        pub jokers: bool,
     }
 
     impl Ord for Hand {
@@ -15,7 +35,7 @@ pub mod common {
                 ('A', 14),
                 ('K', 13),
                 ('Q', 12),
-                ('J', 11),
+                ('J', if self.jokers { 1 } else { 11 }),
                 ('T', 10),
                 ('9', 9),
                 ('8', 8),
@@ -59,8 +79,36 @@ pub mod common {
         }
     }
 
+    impl Ranked for Hand {
+        fn bit(&self) -> i32 {
+            self.bit
+        }
+
+        fn comb_rate(&self) -> usize {
+            self.comb_rate
+        }
+
+        fn comb_name(&self) -> &'static str {
+            COMBINATION_NAMES[self.comb_rate]
+        }
+
+        fn cards(&self) -> String {
+            self.cards.iter().collect()
+        }
+    }
+
     impl Hand {
         pub fn parse(line: &String) -> Hand {
+            Self::parse_with_rules(line, false)
+        }
+
+        /// Part 2 rules: J is a joker, it joins the largest group of cards
+        /// for the combination and is the weakest card on ties
+        pub fn parse_with_jokers(line: &String) -> Hand {
+            Self::parse_with_rules(line, true)
+        }
+
+        fn parse_with_rules(line: &String, jokers: bool) -> Hand {
             let chars: Vec<char> = line.chars().collect();
             let mut bit_str = String::new();
             let mut i = 6;
@@ -75,38 +123,52 @@ pub mod common {
                 cards.push(chars[i]);
             }
             let bit: i32 = bit_str.parse().unwrap();
-            let comb_rate = Self::get_combination(&cards);
+
+            return Self::new(cards, bit, jokers);
+        }
+
+        pub fn new(cards: Vec<char>, bit: i32, jokers: bool) -> Hand {
+            let comb_rate = Self::get_combination(&cards, jokers);
 
             Hand {
                 cards,
                 bit,
                 comb_rate,
+                jokers,
             }
         }
 
-        fn get_combination(cards: &Vec<char>) -> usize {
+        pub fn get_combination(cards: &Vec<char>, jokers: bool) -> usize {
             let mut m = HashMap::new();
 
             for c in cards {
                 *m.entry(c).or_insert(0) += 1;
             }
 
+            if jokers && m.len() > 1 {
+                // Jokers always join the largest group of other cards
+                if let Some(j) = m.remove(&'J') {
+                    let max = m.values_mut().max().unwrap();
+                    *max += j;
+                }
+            }
+
+            let max = *m.values().max().unwrap();
             match m.len() {
                 1 => 6,
                 2 => {
-                    if m[&cards[0]] == 1 || m[&cards[0]] == 4 {
+                    if max == 4 {
                         5
                     } else {
                         4
                     }
                 }
                 3 => {
-                    for v in m.values() {
-                        if *v == 3 {
-                            return 3;
-                        }
+                    if max == 3 {
+                        3
+                    } else {
+                        2
                     }
-                    2
                 }
                 4 => 1,
                 _ => 0,
@@ -116,41 +178,529 @@ pub mod common {
 }
 
 pub mod solution1 {
-    use crate::common::Hand;
+    use crate::common::{Hand, Ranked};
 
     pub fn process(input: &Vec<String>) -> i64 {
         let mut hands = Vec::new();
         for line in input {
             hands.push(Hand::parse(&line));
         }
+
+        return winnings(&mut hands);
+    }
+
+    /// Sorts hands from the weakest to the strongest and sums rank * bit.
+    pub fn winnings<T: Ranked>(hands: &mut Vec<T>) -> i64 {
         let _ = &hands.sort();
 
         let mut res = 0;
         for i in 0..hands.len() {
-            res += (i + 1) as i64 * hands[i].bit as i64;
+            res += (i + 1) as i64 * hands[i].bit() as i64;
         }
 
         return res;
     }
 }
 
+pub mod simulation {
+    use crate::common::Hand;
+
+    pub const LABELS: [char; 13] = [
+        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+    ];
+
+    /// SplitMix64, small and good enough for sampling hands
+    pub struct Rng {
+        state: u64,
+    }
+
+    impl Rng {
+        pub fn new(seed: u64) -> Rng {
+            Rng { state: seed }
+        }
+
+        pub fn next_u64(&mut self) -> u64 {
+            self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = self.state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            return z ^ (z >> 31);
+        }
+
+        /// Returns a number in 0..n
+        pub fn below(&mut self, n: usize) -> usize {
+            return (self.next_u64() % n as u64) as usize;
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Deck {
+        pub cards: Vec<char>,
+        /// Every card is put back after it is drawn
+        pub replacement: bool,
+        /// Hands are ranked with `J` as a joker, see `solution2`
+        pub jokers: bool,
+    }
+
+    impl Deck {
+        /// Every label once, drawn with replacement: all hands are equally likely
+        pub fn uniform(jokers: bool) -> Deck {
+            Deck {
+                cards: LABELS.to_vec(),
+                replacement: true,
+                jokers,
+            }
+        }
+
+        /// `copies` of every label, drawn without replacement
+        pub fn with_copies(copies: usize, jokers: bool) -> Deck {
+            let mut cards = Vec::new();
+            for c in LABELS {
+                for _ in 0..copies {
+                    cards.push(c);
+                }
+            }
+
+            Deck {
+                cards,
+                replacement: false,
+                jokers,
+            }
+        }
+
+        pub fn draw(&self, rng: &mut Rng) -> Hand {
+            let mut cards = Vec::new();
+            if self.replacement {
+                assert!(!self.cards.is_empty(), "Deck is empty");
+                for _ in 0..5 {
+                    cards.push(self.cards[rng.below(self.cards.len())]);
+                }
+            } else {
+                assert!(self.cards.len() >= 5, "Deck is too small");
+                // Partial Fisher-Yates shuffle of the first five cards
+                let mut deck = self.cards.clone();
+                for i in 0..5 {
+                    let j = i + rng.below(deck.len() - i);
+                    deck.swap(i, j);
+                    cards.push(deck[i]);
+                }
+            }
+
+            return Hand::new(cards, 0, self.jokers);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Distribution {
+        pub samples: usize,
+        /// Number of hands by `comb_rate`
+        pub counts: [usize; 7],
+    }
+
+    impl Distribution {
+        pub fn frequency(&self, comb_rate: usize) -> f64 {
+            return self.counts[comb_rate] as f64 / self.samples as f64;
+        }
+    }
+
+    pub fn distribution(deck: &Deck, samples: usize, seed: u64) -> Distribution {
+        assert!(samples > 0, "No samples");
+
+        let mut rng = Rng::new(seed);
+        let mut counts = [0; 7];
+
+        for _ in 0..samples {
+            counts[deck.draw(&mut rng).comb_rate] += 1;
+        }
+
+        return Distribution { samples, counts };
+    }
+
+    /// Expected rank (1 is the weakest) of the hand among `table` hands
+    /// where the others are drawn from the deck. Ties are counted as half.
+    pub fn expected_rank(hand: &Hand, deck: &Deck, table: usize, samples: usize, seed: u64) -> f64 {
+        assert!(table > 0, "Table has no hands");
+        assert!(samples > 0, "No samples");
+        assert!(hand.jokers == deck.jokers, "Hand and deck rank jokers differently");
+
+        let mut rng = Rng::new(seed);
+        let mut beaten = 0.0;
+
+        for _ in 0..samples {
+            let other = deck.draw(&mut rng);
+            if *hand > other {
+                beaten += 1.0;
+            } else if *hand == other {
+                beaten += 0.5;
+            }
+        }
+
+        return 1.0 + (table - 1) as f64 * beaten / samples as f64;
+    }
+}
+
+pub mod poker {
+    use crate::common::Ranked;
+    use crate::solution1::winnings;
+    use core::cmp::Ordering;
+    use std::collections::HashMap;
+
+    pub const HIGH_CARD: usize = 0;
+    pub const ONE_PAIR: usize = 1;
+    pub const TWO_PAIR: usize = 2;
+    pub const THREE_OF_A_KIND: usize = 3;
+    pub const STRAIGHT: usize = 4;
+    pub const FLUSH: usize = 5;
+    pub const FULL_HOUSE: usize = 6;
+    pub const FOUR_OF_A_KIND: usize = 7;
+    pub const STRAIGHT_FLUSH: usize = 8;
+
+    pub const COMBINATION_NAMES: [&str; 9] = [
+        "high card",
+        "one pair",
+        "two pair",
+        "three of a kind",
+        "straight",
+        "flush",
+        "full house",
+        "four of a kind",
+        "straight flush",
+    ];
+
+    const RANKS: [char; 13] = [
+        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+    ];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Card {
+        pub rank: i32,
+        pub suit: char,
+    }
+
+    /// A hand ranked by standard poker rules, e.g. `AhKdQsJcTh 100`.
+    #[derive(Debug, Eq)]
+    pub struct PokerHand {
+        pub cards: Vec<Card>,
+        pub bit: i32,
+        pub comb_rate: usize,
+        /// Card ranks in tie-break order, for example the pair rank
+        /// first and then the kickers from high to low.
+        pub tie_break: Vec<i32>,
+    }
+
+    impl Ord for PokerHand {
+        fn cmp(&self, other: &Self) -> Ordering {
+            if self.comb_rate > other.comb_rate {
+                return Ordering::Greater;
+            }
+
+            if self.comb_rate < other.comb_rate {
+                return Ordering::Less;
+            }
+
+            return self.tie_break.cmp(&other.tie_break);
+        }
+    }
+
+    impl PartialOrd for PokerHand {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for PokerHand {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(&other) == Ordering::Equal
+        }
+    }
+
+    impl Ranked for PokerHand {
+        fn bit(&self) -> i32 {
+            self.bit
+        }
+
+        fn comb_rate(&self) -> usize {
+            self.comb_rate
+        }
+
+        fn comb_name(&self) -> &'static str {
+            COMBINATION_NAMES[self.comb_rate]
+        }
+
+        fn cards(&self) -> String {
+            let mut s = String::new();
+            for c in &self.cards {
+                s.push(RANKS[(c.rank - 2) as usize]);
+                s.push(c.suit);
+            }
+            s
+        }
+    }
+
+    pub fn card_rank(c: char) -> i32 {
+        match c {
+            'A' => 14,
+            'K' => 13,
+            'Q' => 12,
+            'J' => 11,
+            'T' => 10,
+            '2'..='9' => c.to_digit(10).unwrap() as i32,
+            _ => panic!("Unknown card rank: {}", c),
+        }
+    }
+
+    impl PokerHand {
+        pub fn parse(line: &String) -> PokerHand {
+            let (cards_str, bit_str) = line.split_once(' ').expect("Expected cards and bit");
+            let chars: Vec<char> = cards_str.chars().collect();
+            assert!(chars.len() == 10, "Expected 5 suited cards: {}", cards_str);
+
+            let mut cards: Vec<Card> = Vec::new();
+            for i in 0..5 {
+                let suit = chars[i * 2 + 1];
+                match suit {
+                    'h' | 'd' | 's' | 'c' => {}
+                    _ => panic!("Unknown suit: {}", suit),
+                }
+                let card = Card {
+                    rank: card_rank(chars[i * 2]),
+                    suit,
+                };
+                assert!(!cards.contains(&card), "Duplicate card: {}", cards_str);
+                cards.push(card);
+            }
+
+            let bit: i32 = bit_str.trim().parse().unwrap();
+            let (comb_rate, tie_break) = Self::get_combination(&cards);
+
+            PokerHand {
+                cards,
+                bit,
+                comb_rate,
+                tie_break,
+            }
+        }
+
+        /// Returns the combination and the ranks to compare equal combinations by
+        fn get_combination(cards: &Vec<Card>) -> (usize, Vec<i32>) {
+            let mut m: HashMap<i32, usize> = HashMap::new();
+            for c in cards {
+                *m.entry(c.rank).or_insert(0) += 1;
+            }
+
+            // Groups ordered by size and then by rank: full house 3-3-3-2-2
+            // becomes [(3, r1), (2, r2)]
+            let mut groups: Vec<(usize, i32)> = m.iter().map(|(r, n)| (*n, *r)).collect();
+            groups.sort();
+            groups.reverse();
+            let tie_break: Vec<i32> = groups.iter().map(|(_, r)| *r).collect();
+
+            let flush = cards.iter().all(|c| c.suit == cards[0].suit);
+            let straight_high = Self::straight_high(&tie_break);
+
+            if let Some(high) = straight_high {
+                let comb_rate = if flush { STRAIGHT_FLUSH } else { STRAIGHT };
+                return (comb_rate, vec![high]);
+            }
+
+            let comb_rate = match (groups[0].0, groups.len()) {
+                (4, _) => FOUR_OF_A_KIND,
+                (3, 2) => FULL_HOUSE,
+                _ if flush => FLUSH,
+                (3, _) => THREE_OF_A_KIND,
+                (2, 3) => TWO_PAIR,
+                (2, _) => ONE_PAIR,
+                _ => HIGH_CARD,
+            };
+
+            return (comb_rate, tie_break);
+        }
+
+        /// Ranks are expected to be sorted from high to low.
+        /// Returns the highest card of the straight, the wheel (A-2-3-4-5) is 5 high.
+        fn straight_high(ranks: &Vec<i32>) -> Option<i32> {
+            if ranks.len() != 5 {
+                return None;
+            }
+
+            if ranks[0] - ranks[4] == 4 {
+                return Some(ranks[0]);
+            }
+
+            if *ranks == vec![14, 5, 4, 3, 2] {
+                return Some(5);
+            }
+
+            return None;
+        }
+    }
+
+    pub fn process(input: &Vec<String>) -> i64 {
+        let mut hands = Vec::new();
+        for line in input {
+            hands.push(PokerHand::parse(&line));
+        }
+
+        return winnings(&mut hands);
+    }
+}
+
 
 pub mod solution2 {
     use crate::common::Hand;
+    use crate::solution1::winnings;
 
+    /// Winnings with the joker rules, unlike `solution1` which plays J as a jack
     pub fn process(input: &Vec<String>) -> i64 {
         let mut hands = Vec::new();
         for line in input {
-            hands.push(Hand::parse(&line));
+            hands.push(Hand::parse_with_jokers(&line));
         }
-        let _ = &hands.sort();
 
-        let mut res = 0;
-        for i in 0..hands.len() {
-            res += (i + 1) as i64 * hands[i].bit as i64;
+        return winnings(&mut hands);
+    }
+}
+
+pub mod report {
+    use crate::common::{Hand, Ranked};
+
+    #[derive(Debug, PartialEq)]
+    pub struct HandReport {
+        pub cards: String,
+        pub rank: usize,
+        pub comb_rate: usize,
+        pub comb_name: &'static str,
+        pub bit: i32,
+        pub contribution: i64,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct CombinationTotal {
+        pub comb_rate: usize,
+        pub comb_name: &'static str,
+        pub hands: usize,
+        pub bits: i64,
+        pub contribution: i64,
+    }
+
+    /// Breakdown of the winnings: hands from the weakest to the strongest
+    /// and totals per combination from the weakest to the strongest.
+    #[derive(Debug, PartialEq)]
+    pub struct Report {
+        pub hands: Vec<HandReport>,
+        pub totals: Vec<CombinationTotal>,
+        pub total: i64,
+    }
+
+    impl Report {
+        pub fn build<T: Ranked>(hands: &mut Vec<T>) -> Report {
+            let _ = &hands.sort();
+
+            let mut report = Report {
+                hands: Vec::new(),
+                totals: Vec::new(),
+                total: 0,
+            };
+
+            for i in 0..hands.len() {
+                let hand = &hands[i];
+                let contribution = (i + 1) as i64 * hand.bit() as i64;
+                report.hands.push(HandReport {
+                    cards: hand.cards(),
+                    rank: i + 1,
+                    comb_rate: hand.comb_rate(),
+                    comb_name: hand.comb_name(),
+                    bit: hand.bit(),
+                    contribution,
+                });
+                report.total += contribution;
+
+                // Hands are sorted, so equal combinations are next to each other
+                match report.totals.last_mut() {
+                    Some(t) if t.comb_rate == hand.comb_rate() => {
+                        t.hands += 1;
+                        t.bits += hand.bit() as i64;
+                        t.contribution += contribution;
+                    }
+                    _ => report.totals.push(CombinationTotal {
+                        comb_rate: hand.comb_rate(),
+                        comb_name: hand.comb_name(),
+                        hands: 1,
+                        bits: hand.bit() as i64,
+                        contribution,
+                    }),
+                }
+            }
+
+            return report;
         }
 
-        return res;
+        pub fn to_csv(&self) -> String {
+            let mut out = String::from("rank,cards,combination,bit,contribution\n");
+            for h in &self.hands {
+                out.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    h.rank, h.cards, h.comb_name, h.bit, h.contribution
+                ));
+            }
+
+            return out;
+        }
+
+        pub fn totals_to_csv(&self) -> String {
+            let mut out = String::from("combination,hands,bits,contribution\n");
+            for t in &self.totals {
+                out.push_str(&format!(
+                    "{},{},{},{}\n",
+                    t.comb_name, t.hands, t.bits, t.contribution
+                ));
+            }
+
+            return out;
+        }
+
+        pub fn to_json(&self) -> String {
+            let hands: Vec<String> = self
+                .hands
+                .iter()
+                .map(|h| {
+                    format!(
+                        "{{\"rank\":{},\"cards\":\"{}\",\"combination\":\"{}\",\"bit\":{},\"contribution\":{}}}",
+                        h.rank, h.cards, h.comb_name, h.bit, h.contribution
+                    )
+                })
+                .collect();
+
+            let totals: Vec<String> = self
+                .totals
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{{\"combination\":\"{}\",\"hands\":{},\"bits\":{},\"contribution\":{}}}",
+                        t.comb_name, t.hands, t.bits, t.contribution
+                    )
+                })
+                .collect();
+
+            return format!(
+                "{{\"total\":{},\"hands\":[{}],\"totals\":[{}]}}",
+                self.total,
+                hands.join(","),
+                totals.join(",")
+            );
+        }
+    }
+
+    /// Report for the standard rules, see `solution1`
+    pub fn standard(input: &Vec<String>) -> Report {
+        let mut hands: Vec<Hand> = input.iter().map(Hand::parse).collect();
+        return Report::build(&mut hands);
+    }
+
+    /// Report for the joker rules, see `solution2`
+    pub fn jokers(input: &Vec<String>) -> Report {
+        let mut hands: Vec<Hand> = input.iter().map(Hand::parse_with_jokers).collect();
+        return Report::build(&mut hands);
     }
 }
 #[cfg(test)]
@@ -175,6 +725,245 @@ mod tests1 {
 }
 
 
+#[cfg(test)]
+mod tests_poker {
+    use super::*;
+    use poker::PokerHand;
+
+    fn hand(s: &str) -> PokerHand {
+        PokerHand::parse(&format!("{} 1", s))
+    }
+
+    #[test]
+    fn combinations() {
+        assert_eq!(hand("2h7d9sJcKh").comb_rate, poker::HIGH_CARD);
+        assert_eq!(hand("2h2d9sJcKh").comb_rate, poker::ONE_PAIR);
+        assert_eq!(hand("2h2d9s9cKh").comb_rate, poker::TWO_PAIR);
+        assert_eq!(hand("2h2d2s9cKh").comb_rate, poker::THREE_OF_A_KIND);
+        assert_eq!(hand("9h8dTs7cJh").comb_rate, poker::STRAIGHT);
+        assert_eq!(hand("Ah2d3s4c5h").comb_rate, poker::STRAIGHT);
+        assert_eq!(hand("2h7h9hJhKh").comb_rate, poker::FLUSH);
+        assert_eq!(hand("2h2d2s9c9h").comb_rate, poker::FULL_HOUSE);
+        assert_eq!(hand("2h2d2s2cKh").comb_rate, poker::FOUR_OF_A_KIND);
+        assert_eq!(hand("9s8sTs7sJs").comb_rate, poker::STRAIGHT_FLUSH);
+        assert_eq!(hand("As2s3s4s5s").comb_rate, poker::STRAIGHT_FLUSH);
+    }
+
+    #[test]
+    fn tie_breaks() {
+        // The wheel is the lowest straight
+        assert!(hand("Ah2d3s4c5h") < hand("2h3d4s5c6h"));
+        // Pair rank goes before kickers
+        assert!(hand("3h3dAsKcQh") < hand("4h4d2s5c6h"));
+        // Kickers are compared from high to low
+        assert!(hand("9h9dAsKc2h") > hand("9s9cAhQd8s"));
+        // Card order does not matter
+        assert!(hand("Kh2d9sJc7h") == hand("2c7s9dJhKd"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate card")]
+    fn duplicate_cards() {
+        hand("AhAhAhAhAh");
+    }
+
+    #[test]
+    fn process_works() {
+        let input = vec![
+            "AhKhQhJhTh 1",
+            "2c2d2h3s3c 10",
+            "Ah2d3s4c5h 100",
+            "7s7d9c9hKd 1000",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        assert_eq!(poker::process(&input), 1000 + 2 * 100 + 3 * 10 + 4 * 1);
+    }
+}
+
+#[cfg(test)]
+mod tests_report {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        vec![
+            "32T3K 765",
+            "T55J5 684",
+            "KK677 28",
+            "KTJJT 220",
+            "QQQJA 483",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn standard_report() {
+        let report = report::standard(&example());
+
+        assert_eq!(report.total, solution1::process(&example()));
+        assert_eq!(report.hands[0].cards, "32T3K");
+        assert_eq!(report.hands[0].comb_name, "one pair");
+        assert_eq!(report.hands[0].contribution, 765);
+
+        let totals: Vec<(&str, usize, i64)> = report
+            .totals
+            .iter()
+            .map(|t| (t.comb_name, t.hands, t.contribution))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("one pair", 1, 765),
+                ("two pair", 2, 220 * 2 + 28 * 3),
+                ("three of a kind", 2, 684 * 4 + 483 * 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn joker_report() {
+        let report = report::jokers(&example());
+
+        assert_eq!(report.total, 5905);
+        assert_eq!(report.hands[4].cards, "KTJJT");
+        assert_eq!(report.hands[4].comb_name, "four of a kind");
+    }
+
+    #[test]
+    fn export() {
+        let input = vec!["32T3K 765".to_string(), "KK677 28".to_string()];
+        let report = report::standard(&input);
+
+        assert_eq!(
+            report.to_csv(),
+            "rank,cards,combination,bit,contribution\n1,32T3K,one pair,765,765\n2,KK677,two pair,28,56\n"
+        );
+        assert_eq!(
+            report.totals_to_csv(),
+            "combination,hands,bits,contribution\none pair,1,765,765\ntwo pair,1,28,56\n"
+        );
+        assert_eq!(
+            report.to_json(),
+            "{\"total\":821,\"hands\":[\
+            {\"rank\":1,\"cards\":\"32T3K\",\"combination\":\"one pair\",\"bit\":765,\"contribution\":765},\
+            {\"rank\":2,\"cards\":\"KK677\",\"combination\":\"two pair\",\"bit\":28,\"contribution\":56}],\
+            \"totals\":[\
+            {\"combination\":\"one pair\",\"hands\":1,\"bits\":765,\"contribution\":765},\
+            {\"combination\":\"two pair\",\"hands\":1,\"bits\":28,\"contribution\":56}]}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_simulation {
+    use super::*;
+    use common::Hand;
+    use simulation::{Deck, Rng};
+
+    #[test]
+    fn uniform_deck_matches_exact_probabilities() {
+        // Ordered hands out of 13^5 for every combination
+        let exact = [154440, 171600, 25740, 17160, 1560, 780, 13];
+        let total = 13_f64.powi(5);
+
+        let d = simulation::distribution(&Deck::uniform(false), 20000, 42);
+        assert_eq!(d.counts.iter().sum::<usize>(), 20000);
+
+        for comb_rate in 0..7 {
+            let p = exact[comb_rate] as f64 / total;
+            assert!(
+                (d.frequency(comb_rate) - p).abs() < 0.01,
+                "comb_rate {}: {} vs {}",
+                comb_rate,
+                d.frequency(comb_rate),
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let deck = Deck::with_copies(4, true);
+        assert_eq!(
+            simulation::distribution(&deck, 1000, 7),
+            simulation::distribution(&deck, 1000, 7)
+        );
+    }
+
+    #[test]
+    fn without_replacement_has_no_five_of_a_kind() {
+        let d = simulation::distribution(&Deck::with_copies(4, false), 5000, 1);
+        assert_eq!(d.counts[6], 0);
+    }
+
+    #[test]
+    fn jokers_make_hands_stronger() {
+        let plain = simulation::distribution(&Deck::uniform(false), 5000, 3);
+        let jokers = simulation::distribution(&Deck::uniform(true), 5000, 3);
+        assert!(jokers.counts[0] < plain.counts[0]);
+    }
+
+    #[test]
+    fn expected_rank() {
+        let deck = Deck::uniform(false);
+        let best = Hand::new(vec!['A'; 5], 0, false);
+        let worst = Hand::new(vec!['2', '3', '4', '5', '7'], 0, false);
+
+        assert!(simulation::expected_rank(&best, &deck, 5, 1000, 1) > 4.99);
+        assert!(simulation::expected_rank(&worst, &deck, 5, 1000, 1) < 1.01);
+    }
+
+    #[test]
+    fn rng_below_stays_in_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            assert!(rng.below(13) < 13);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Deck is empty")]
+    fn empty_deck() {
+        let deck = Deck {
+            cards: Vec::new(),
+            replacement: true,
+            jokers: false,
+        };
+        deck.draw(&mut Rng::new(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Table has no hands")]
+    fn empty_table() {
+        let hand = Hand::parse(&"AAAAA 1".to_string());
+        simulation::expected_rank(&hand, &Deck::uniform(false), 0, 100, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No samples")]
+    fn no_samples() {
+        let hand = Hand::parse(&"AAAAA 1".to_string());
+        simulation::expected_rank(&hand, &Deck::uniform(false), 4, 0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No samples")]
+    fn empty_distribution() {
+        simulation::distribution(&Deck::uniform(false), 0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "rank jokers differently")]
+    fn jokers_mismatch() {
+        let hand = Hand::parse_with_jokers(&"JJJJJ 1".to_string());
+        simulation::expected_rank(&hand, &Deck::uniform(false), 4, 100, 1);
+    }
+}
+
 #[cfg(test)]
 mod tests2 {
     use super::*;
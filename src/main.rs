@@ -1,6 +1,74 @@
 use common::adv_io;
 
 fn main() {
+    // Day 1 trace: cargo run -- d1-trace d1/input
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 2 && args[1] == "d1-trace" {
+        let input = adv_io::read_input(&args[2]);
+        let vocabulary = d1::solution2::Vocabulary::english();
+        let traces = d1::solution2::trace(&input, &vocabulary);
+        for (line, trace) in input.iter().zip(traces) {
+            match trace {
+                Some(t) => println!(
+                    "{} => {} ({:?} {}..{}, {:?} {}..{})",
+                    t.highlight(line),
+                    t.value,
+                    t.first.kind,
+                    t.first.start,
+                    t.first.end,
+                    t.last.kind,
+                    t.last.start,
+                    t.last.end
+                ),
+                None => println!("{} => no digits", line),
+            }
+        }
+        return;
+    }
+
+    // Day 2 stats: cargo run -- d2-stats d2/input [csv|json]
+    if args.len() > 2 && args[1] == "d2-stats" {
+        let input = adv_io::read_input(&args[2]);
+        let stats = d2::stats::Stats::from_input(&input);
+        if args.len() > 3 && args[3] == "json" {
+            println!("{}", stats.to_json());
+        } else {
+            print!("{}", stats.to_csv());
+        }
+        return;
+    }
+
+    // Day 3 rendering: cargo run -- d3-render d3/input [svg]
+    if args.len() > 2 && args[1] == "d3-render" {
+        let input = adv_io::read_input(&args[2]);
+        let rules = d3::common::Rules::default();
+        if args.len() > 3 && args[3] == "svg" {
+            print!(
+                "{}",
+                d3::render::svg(&input, &rules).expect("Malformed schematic")
+            );
+        } else {
+            print!(
+                "{}",
+                d3::render::ansi(&input, &rules).expect("Malformed schematic")
+            );
+        }
+        return;
+    }
+
+    // Day 3 streaming: cat d3/input | cargo run -- d3-stream
+    if args.len() > 1 && args[1] == "d3-stream" {
+        let stdin = std::io::stdin();
+        let totals = d3::stream::process_reader(stdin.lock(), &d3::common::Rules::default())
+            .expect("Could not process stdin");
+        println!("{}", totals.parts);
+        match totals.gears {
+            Some(gears) => println!("{}", gears),
+            None => println!("gear ratio overflow"),
+        }
+        return;
+    }
+
     // Day 1
     // let input = adv_io::read_input("d1/input");
     // let result1 = d1::solution1::sum_of_colibration_values(&input);